@@ -16,14 +16,50 @@ fn main() {
     }));
     words.sort_unstable_by_key(|&(_, count)| std::cmp::Reverse(count));
 
+    // One dictionary per word length, each sorted by frequency.
+    let mut lengths = Vec::from_iter(words.iter().map(|(word, _)| word.len()));
+    lengths.sort_unstable();
+    lengths.dedup();
+
+    for &len in &lengths {
+        let words = Vec::from_iter(words.iter().filter(|(word, _)| word.len() == len));
+        writeln!(
+            f,
+            "pub static DICTIONARY_{}: [(&str, usize); {}] = [",
+            len,
+            words.len()
+        )
+        .unwrap();
+        for (word, count) in words {
+            writeln!(f, "(\"{}\", {}),", word, count).unwrap();
+        }
+        writeln!(f, "];").unwrap();
+    }
+
+    if lengths.contains(&5) {
+        writeln!(
+            f,
+            "pub static DICTIONARY: &[(&str, usize)] = &DICTIONARY_5;"
+        )
+        .unwrap();
+    } else {
+        writeln!(f, "pub static DICTIONARY: &[(&str, usize)] = &[];").unwrap();
+    }
+
+    writeln!(
+        f,
+        "/// The embedded dictionary of `len` letter words, most frequent first."
+    )
+    .unwrap();
     writeln!(
         f,
-        "pub const DICTIONARY: [(&str, usize); {}] = [",
-        words.len()
+        "pub fn dictionary(len: usize) -> &'static [(&'static str, usize)] {{"
     )
     .unwrap();
-    for (word, count) in words {
-        writeln!(f, "(\"{}\", {}),", word, count).unwrap();
+    writeln!(f, "match len {{").unwrap();
+    for len in lengths {
+        writeln!(f, "{} => &DICTIONARY_{},", len, len).unwrap();
     }
-    write!(f, "];").unwrap();
+    writeln!(f, "_ => &[],").unwrap();
+    write!(f, "}}}}").unwrap();
 }
@@ -0,0 +1,149 @@
+use crate::{
+    patterns::{self, Row},
+    Options, Pattern,
+};
+use std::sync::Arc;
+
+/// A set of word indices of a dictionary, with a bit for every word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Bitset {
+    blocks: Box<[u64]>,
+}
+
+impl Bitset {
+    /// An empty set for a dictionary of `len` words.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            blocks: vec![0; len.div_ceil(64)].into_boxed_slice(),
+        }
+    }
+
+    /// The set of `indices`, for a dictionary of `len` words.
+    pub(crate) fn with_indices(len: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        let mut set = Self::new(len);
+        for idx in indices {
+            set.insert(idx);
+        }
+        set
+    }
+
+    pub(crate) fn insert(&mut self, idx: usize) {
+        self.blocks[idx / 64] |= 1 << (idx % 64);
+    }
+
+    pub(crate) fn remove(&mut self, idx: usize) {
+        self.blocks[idx / 64] &= !(1 << (idx % 64));
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, idx: usize) -> bool {
+        self.blocks[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    /// Keep only the words that are also in `other`.
+    pub(crate) fn intersect(&mut self, other: &Self) {
+        for (block, other) in self.blocks.iter_mut().zip(&*other.blocks) {
+            *block &= other;
+        }
+    }
+
+    /// Add every word of `other`.
+    pub(crate) fn union(&mut self, other: &Self) {
+        for (block, other) in self.blocks.iter_mut().zip(&*other.blocks) {
+            *block |= other;
+        }
+    }
+
+    /// The number of words in the set.
+    pub(crate) fn len(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|block| block.count_ones() as usize)
+            .sum()
+    }
+
+    /// The indices of the words in the set, in increasing order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks.iter().enumerate().flat_map(|(i, &block)| {
+            let mut rest = block;
+            std::iter::from_fn(move || {
+                (rest != 0).then(|| {
+                    let bit = rest.trailing_zeros() as usize;
+                    rest &= rest - 1;
+                    i * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+/// Keep only the words of `set` that get `pattern` when the word with index `guess_idx` is
+/// guessed. `remaining` must hold the same words as `set`.
+///
+/// If the cache is enabled, the words of the whole dictionary that get each pattern are kept in it
+/// for guesses that have been made while many candidates remained, since the same feedback comes
+/// up again and again over many games, and narrowing `set` down is then a matter of a word-wise
+/// AND. Once few candidates remain, feedback rarely repeats, so only those candidates are checked.
+pub(crate) fn narrow<const N: usize>(
+    options: &Options<N>,
+    set: &mut Bitset,
+    remaining: &[(usize, f64)],
+    guess_idx: usize,
+    pattern: Pattern<N>,
+) {
+    let dictionary = &options.dictionary;
+    if options.cache && remaining.len() * FEW >= dictionary.len() {
+        set.intersect(&matching(options, guess_idx, pattern));
+    } else {
+        let reference = pattern.index();
+        options.for_each_pattern(guess_idx, remaining, |&(word_idx, _), pattern| {
+            if pattern != reference {
+                set.remove(word_idx);
+            }
+        });
+    }
+}
+
+/// Feedback is only kept while more than one in this many words of the dictionary remain.
+const FEW: usize = 16;
+
+/// The words of the dictionary that get `pattern` when the word with index `guess_idx` is
+/// guessed, which are kept in the cache for later if there is room.
+fn matching<const N: usize>(
+    options: &Options<N>,
+    guess_idx: usize,
+    pattern: Pattern<N>,
+) -> Arc<Bitset> {
+    let dictionary = &options.dictionary;
+    let rows = options.cache_rows;
+    let cached = patterns::with(dictionary, rows, |cache| {
+        cache.matching(guess_idx, pattern.index())
+    });
+    cached.unwrap_or_else(|| {
+        let guess = dictionary.word(guess_idx);
+        let reference = pattern.index();
+        let mut set = Bitset::new(dictionary.len());
+        options.with_row(guess_idx, |row| {
+            if let Row::Uncached = row {
+                let mut patterns = Vec::new();
+                dictionary.packed().patterns(guess, &mut patterns);
+                for (idx, &pattern) in patterns.iter().enumerate() {
+                    if pattern == reference {
+                        set.insert(idx);
+                    }
+                }
+            } else {
+                for (idx, (answer, _)) in dictionary.words().enumerate() {
+                    if row.get::<N>(guess, answer, idx) == reference {
+                        set.insert(idx);
+                    }
+                }
+            }
+        });
+        let set = Arc::new(set);
+        patterns::with(dictionary, rows, |cache| {
+            cache.keep_matching(guess_idx, pattern.index(), Arc::clone(&set));
+        });
+        set
+    })
+}
@@ -0,0 +1,212 @@
+use crate::{dictionary::fnv1a, Correctness, Dictionary, Guess, Guesser, Options, Pattern};
+use std::{borrow::Cow, fmt, io, path::Path};
+
+/// An opening book: the first guess, and the best second guess after each pattern that the first
+/// guess may get, as worked out by a [`Solver`](crate::Solver) with one set of [`Options`].
+///
+/// A book only holds for the dictionary, answers and options it was generated with. Solvers ignore
+/// a book that was generated for anything else, and [`Book::check`] tells why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Book<const N: usize = 5> {
+    checksum: u64,
+    fingerprint: u64,
+    opener: String,
+    /// The second guess for each pattern by its index, if some word gets that pattern.
+    seconds: Vec<Option<String>>,
+}
+
+/// An error returned when a [`Book`] could not be loaded, or does not fit a set of options.
+#[derive(Debug)]
+pub enum BookError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// A line was not a key followed by its value, or a pattern followed by a word.
+    Parse { line: usize, content: String },
+
+    /// The book was generated for another dictionary, or other answers.
+    OtherDictionary,
+
+    /// The book was generated with other options.
+    OtherOptions,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read opening book: {}", e),
+            Self::Parse { line, content } => write!(
+                f,
+                "line {} of the opening book is not valid, found '{}'",
+                line, content
+            ),
+            Self::OtherDictionary => write!(
+                f,
+                "the opening book was generated for another dictionary or other answers and must \
+                 be regenerated"
+            ),
+            Self::OtherOptions => write!(
+                f,
+                "the opening book was generated with other options and must be regenerated"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BookError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl<const N: usize> Book<N> {
+    /// Work out the first guess that a solver with `options` makes, and its second guess after
+    /// every pattern that some candidate gets. Any book in `options` is not used.
+    pub fn generate(options: &Options<N>) -> Self {
+        let options = Options {
+            book: None,
+            ..options.clone()
+        };
+        let mut solver = options.clone().build();
+        let opener = solver.guess(&[]);
+
+        // Only the candidates can be the answer, so the feedback of other words never comes up.
+        let candidates: Vec<String> = solver.candidates().map(str::to_string).collect();
+        let solved = Pattern([Correctness::Correct; N]).index();
+        let mut seconds = vec![None; Pattern::<N>::COUNT];
+        for word in &candidates {
+            let mask = Correctness::compute(word, &opener);
+            let idx = Pattern(mask).index();
+            if idx != solved && seconds[idx].is_none() {
+                let history = [Guess {
+                    word: Cow::Borrowed(&*opener),
+                    mask,
+                }];
+                seconds[idx] = Some(solver.guess(&history));
+            }
+        }
+
+        Self {
+            checksum: checksum(&options.dictionary),
+            fingerprint: options.fingerprint(),
+            opener,
+            seconds,
+        }
+    }
+
+    /// Check that this book was generated for the dictionary, answers and options of `options`.
+    pub fn check(&self, options: &Options<N>) -> Result<(), BookError> {
+        if self.checksum != checksum(&options.dictionary) {
+            Err(BookError::OtherDictionary)
+        } else if self.fingerprint != options.fingerprint() {
+            Err(BookError::OtherOptions)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The first guess.
+    pub fn opener(&self) -> &str {
+        &self.opener
+    }
+
+    /// The second guess after the opener gets `pattern`, if some word gets that pattern.
+    pub fn second(&self, pattern: Pattern<N>) -> Option<&str> {
+        self.seconds[pattern.index()].as_deref()
+    }
+
+    /// Parse a book in the format it is displayed in: the checksum of the dictionary and its
+    /// answers, the options fingerprint and the opener, each on a line after its key, followed by
+    /// one line per pattern with the pattern and the second guess.
+    pub fn parse(text: &str) -> Result<Self, BookError> {
+        let mut checksum = None;
+        let mut fingerprint = None;
+        let mut opener = None;
+        let mut seconds = vec![None; Pattern::<N>::COUNT];
+        for (i, line) in text.lines().enumerate() {
+            let mut parts = line.split_whitespace();
+            let parsed = match (parts.next(), parts.next(), parts.next()) {
+                (None, _, _) => continue,
+                (Some("dictionary"), Some(value), None) => u64::from_str_radix(value, 16)
+                    .ok()
+                    .map(|value| checksum = Some(value)),
+                (Some("options"), Some(value), None) => u64::from_str_radix(value, 16)
+                    .ok()
+                    .map(|value| fingerprint = Some(value)),
+                (Some("opener"), Some(word), None) if is_word::<N>(word) => {
+                    opener = Some(word.to_string());
+                    Some(())
+                }
+                (Some(pattern), Some(word), None) if is_word::<N>(word) => {
+                    pattern.parse::<Pattern<N>>().ok().map(|pattern| {
+                        seconds[pattern.index()] = Some(word.to_string());
+                    })
+                }
+                _ => None,
+            };
+            if parsed.is_none() {
+                return Err(BookError::Parse {
+                    line: i + 1,
+                    content: line.to_string(),
+                });
+            }
+        }
+
+        match (checksum, fingerprint, opener) {
+            (Some(checksum), Some(fingerprint), Some(opener)) => Ok(Self {
+                checksum,
+                fingerprint,
+                opener,
+                seconds,
+            }),
+            _ => Err(BookError::Parse {
+                line: text.lines().count(),
+                content: "a book needs a dictionary, options and opener line".to_string(),
+            }),
+        }
+    }
+
+    /// Load a book in the format of [`Book::parse`] from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Save this book to a file, in the format of [`Book::parse`].
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+/// A checksum of the words of `dictionary` along with its answers, since a prior may weigh the
+/// words by the answers.
+fn checksum<const N: usize>(dictionary: &Dictionary<N>) -> u64 {
+    let checksums = [dictionary.checksum(), dictionary.answers_checksum()];
+    fnv1a(checksums.iter().flat_map(|checksum| checksum.to_le_bytes()))
+}
+
+fn is_word<const N: usize>(word: &str) -> bool {
+    word.len() == N && word.bytes().all(|b| b.is_ascii_lowercase())
+}
+
+impl<const N: usize> fmt::Display for Book<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "dictionary {:016x}", self.checksum)?;
+        writeln!(f, "options {:016x}", self.fingerprint)?;
+        writeln!(f, "opener {}", self.opener)?;
+        for (idx, second) in self.seconds.iter().enumerate() {
+            if let Some(second) = second {
+                writeln!(f, "{} {}", Pattern::<N>::from_index(idx), second)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{patterns::KeepAlive, PackedWords};
+use once_cell::sync::OnceCell;
+use std::{
+    any::Any,
+    collections::{hash_map::Entry, HashMap},
+    fmt, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+const ANSWERS: &str = include_str!("../answers.txt");
+
+/// The words of the embedded dictionaries, built at most once per word length.
+static EMBEDDED: OnceCell<Mutex<HashMap<usize, Arc<dyn Any + Send + Sync>>>> = OnceCell::new();
+
+/// The `N` letter words that may be guessed, how often each occurs, and the words that may be
+/// answers.
+///
+/// Words are kept most frequent first, and are referred to by their position in that order.
+#[derive(Clone)]
+pub struct Dictionary<const N: usize = 5> {
+    words: Vec<(String, usize)>,
+    index: HashMap<[u8; N], usize>,
+    answers: Vec<usize>,
+    checksum: u64,
+    answers_checksum: u64,
+    /// The words packed for computing many patterns at once, once they are needed.
+    packed: OnceCell<PackedWords<N>>,
+    /// The shared caches made for this dictionary, which copies of it share.
+    keep_alive: KeepAlive,
+}
+
+impl<const N: usize> fmt::Debug for Dictionary<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("words", &self.words.len())
+            .field("answers", &self.answers.len())
+            .field("checksum", &format_args!("{:016x}", self.checksum))
+            .field(
+                "answers_checksum",
+                &format_args!("{:016x}", self.answers_checksum),
+            )
+            .finish()
+    }
+}
+
+/// An error returned when a [`Dictionary`] or its answers could not be loaded.
+#[derive(Debug)]
+pub enum DictionaryError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// A line was not a word optionally followed by its frequency.
+    Parse { line: usize, content: String },
+
+    /// A word did not have the dictionary's length, or was not made of ascii letters.
+    Word { word: String, expected_len: usize },
+
+    /// A word appeared more than once.
+    Duplicate(String),
+
+    /// An answer was not one of the dictionary's words.
+    UnknownAnswer(String),
+
+    /// There were no words at all.
+    Empty,
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read word list: {}", e),
+            Self::Parse { line, content } => write!(
+                f,
+                "line {} should be a word and optionally its frequency, found '{}'",
+                line, content
+            ),
+            Self::Word { word, expected_len } => write!(
+                f,
+                "'{}' is not a word of {} ascii letters",
+                word, expected_len
+            ),
+            Self::Duplicate(word) => write!(f, "'{}' appears more than once", word),
+            Self::UnknownAnswer(word) => write!(f, "answer '{}' is not in the dictionary", word),
+            Self::Empty => write!(f, "the dictionary has no words"),
+        }
+    }
+}
+
+impl std::error::Error for DictionaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DictionaryError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl Dictionary {
+    /// The dictionary of five letter words compiled into Popoki, along with its answers.
+    pub fn embedded() -> Arc<Self> {
+        Self::try_embedded().expect("five letter words are embedded")
+    }
+}
+
+impl<const N: usize> Dictionary<N> {
+    /// The dictionary compiled into Popoki for `N` letter words, along with its answers.
+    ///
+    /// Popoki only embeds five letter words, so this fails with [`DictionaryError::Empty`] for
+    /// other lengths.
+    pub fn try_embedded() -> Result<Arc<Self>, DictionaryError> {
+        let mut embedded = EMBEDDED.get_or_init(Default::default).lock().unwrap();
+        let dictionary = match embedded.entry(N) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let words = crate::dictionary(N).iter().copied();
+                let answers = ANSWERS.split_whitespace().filter(|word| word.len() == N);
+                let dictionary = Self::from_words(words)?.with_answers(answers)?;
+                entry.insert(Arc::new(dictionary))
+            }
+        };
+        Ok(Arc::clone(dictionary)
+            .downcast()
+            .expect("embedded dictionaries are keyed by their length"))
+    }
+
+    /// Build a dictionary from words and how often each of them occurs.
+    ///
+    /// Words are lowercased, and may be given in any order. The dictionary has no answers until
+    /// [`Dictionary::with_answers`] is called.
+    pub fn from_words<I, W>(words: I) -> Result<Self, DictionaryError>
+    where
+        I: IntoIterator<Item = (W, usize)>,
+        W: Into<String>,
+    {
+        let mut words = words
+            .into_iter()
+            .map(|(word, count)| {
+                let word = word.into().to_ascii_lowercase();
+                if word.len() != N || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+                    return Err(DictionaryError::Word {
+                        word,
+                        expected_len: N,
+                    });
+                }
+                Ok((word, count))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if words.is_empty() {
+            return Err(DictionaryError::Empty);
+        }
+        // A stable sort, so that words that are equally common keep the order they were given in.
+        words.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+        let mut index = HashMap::with_capacity(words.len());
+        for (idx, (word, _)) in words.iter().enumerate() {
+            if index.insert(letters(word), idx).is_some() {
+                return Err(DictionaryError::Duplicate(word.clone()));
+            }
+        }
+
+        Ok(Self {
+            checksum: checksum(words.iter().map(|(word, count)| (&**word, *count))),
+            words,
+            index,
+            answers: Vec::new(),
+            answers_checksum: fnv1a([]),
+            packed: OnceCell::new(),
+            keep_alive: KeepAlive::default(),
+        })
+    }
+
+    /// Parse a dictionary with one word per line, each optionally followed by a space and how
+    /// often it occurs. Words without a frequency count once.
+    pub fn parse(text: &str) -> Result<Self, DictionaryError> {
+        let words = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let mut parts = line.split_whitespace();
+                let word = parts.next().unwrap_or_default();
+                let count = parts.next().map_or(Some(1), |count| count.parse().ok());
+                match (count, parts.next()) {
+                    (Some(count), None) => Ok((word, count)),
+                    _ => Err(DictionaryError::Parse {
+                        line: i + 1,
+                        content: line.to_string(),
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_words(words)
+    }
+
+    /// Load a dictionary in the format of [`Dictionary::parse`] from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Replace the answers of this dictionary. Every answer must be one of its words.
+    pub fn with_answers<I, W>(mut self, answers: I) -> Result<Self, DictionaryError>
+    where
+        I: IntoIterator<Item = W>,
+        W: AsRef<str>,
+    {
+        self.answers = answers
+            .into_iter()
+            .map(|answer| {
+                let answer = answer.as_ref().to_ascii_lowercase();
+                self.index_of(&answer)
+                    .ok_or(DictionaryError::UnknownAnswer(answer))
+            })
+            .collect::<Result<_, _>>()?;
+        self.answers_checksum = fnv1a(self.answers().flat_map(str::bytes));
+        Ok(self)
+    }
+
+    /// Load the answers of this dictionary from a file of whitespace-separated words.
+    pub fn load_answers(self, path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        let answers = std::fs::read_to_string(path)?;
+        self.with_answers(answers.split_whitespace())
+    }
+
+    /// The number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The word at position `idx`.
+    pub fn word(&self, idx: usize) -> &str {
+        &self.words[idx].0
+    }
+
+    /// How often the word at position `idx` occurs.
+    pub fn count(&self, idx: usize) -> usize {
+        self.words[idx].1
+    }
+
+    /// The position of `word` in the dictionary, if it is in there.
+    pub fn index_of(&self, word: &str) -> Option<usize> {
+        if word.len() != N {
+            return None;
+        }
+        self.index.get(&letters(word)).copied()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.index_of(word).is_some()
+    }
+
+    /// Every word with how often it occurs, most frequent first.
+    pub fn words(&self) -> impl DoubleEndedIterator<Item = (&str, usize)> + '_ {
+        self.words.iter().map(|(word, count)| (&**word, *count))
+    }
+
+    /// What keeps the shared caches made for this dictionary alive.
+    pub(crate) fn keep_alive(&self) -> &KeepAlive {
+        &self.keep_alive
+    }
+
+    /// Every word, packed for [`PackedWords::patterns`].
+    pub(crate) fn packed(&self) -> &PackedWords<N> {
+        self.packed
+            .get_or_init(|| PackedWords::new(self.words().map(|(word, _)| word)))
+    }
+
+    /// The words that may be answers, in the order they were given.
+    pub fn answers(&self) -> impl Iterator<Item = &str> + '_ {
+        self.answers.iter().map(|&idx| self.word(idx))
+    }
+
+    /// A checksum of the words and their frequencies, which identifies the dictionary.
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+
+    /// A checksum of the answers, which tells apart dictionaries that only differ in their answers.
+    pub fn answers_checksum(&self) -> u64 {
+        self.answers_checksum
+    }
+}
+
+fn letters<const N: usize>(word: &str) -> [u8; N] {
+    let mut letters = [0; N];
+    letters.copy_from_slice(word.as_bytes());
+    letters
+}
+
+/// 64-bit FNV-1a over the words and their counts.
+fn checksum<'a>(words: impl Iterator<Item = (&'a str, usize)>) -> u64 {
+    fnv1a(words.flat_map(|(word, count)| word.bytes().chain((count as u64).to_le_bytes())))
+}
+
+/// 64-bit FNV-1a over `bytes`.
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,171 @@
+use crate::{Correctness, HardMode, Options, Pattern};
+use std::collections::{HashMap, HashSet};
+
+/// The expected number of guesses and the best guess for each candidate set that has been searched,
+/// keyed by the indices of its words and the number of guesses left, if that is limited.
+pub(crate) type Memo = HashMap<(Vec<usize>, Option<usize>), (f64, usize)>;
+
+/// Find the guess that minimises the expected number of guesses needed to find the answer among
+/// `candidates`, along with that number, by trying every guess at every step.
+///
+/// With `guesses_left`, only strategies that always find the answer within that many guesses
+/// count, and `None` is returned if there is no such strategy.
+///
+/// `candidates` must be in dictionary order, which is how the solver keeps them, so that the same
+/// set always has the same key in `memo`.
+pub(crate) fn best_guess<const N: usize>(
+    options: &Options<N>,
+    candidates: &[(usize, f64)],
+    guesses_left: Option<usize>,
+    memo: &mut Memo,
+) -> Option<(f64, usize)> {
+    let mut search = Search {
+        options,
+        memo,
+        // Any word that honours the hints of hard mode may be guessed, but which words those are
+        // depends on the whole history. Candidates always honour them, so hard mode sticks to
+        // those.
+        any_word: options.hard_mode == HardMode::Easy,
+    };
+    let (cost, guess) = search.solve(candidates, guesses_left);
+    cost.is_finite().then_some((cost, guess))
+}
+
+struct Search<'a, const N: usize> {
+    options: &'a Options<N>,
+    memo: &'a mut Memo,
+    any_word: bool,
+}
+
+/// How a guess splits up a candidate set.
+struct Split {
+    guess: usize,
+
+    /// A lower bound on the expected number of guesses when making this guess.
+    bound: f64,
+
+    /// The pattern, word index and probability of each candidate, grouped by pattern.
+    patterns: Vec<(usize, usize, f64)>,
+}
+
+/// A lower bound on the expected number of guesses needed to find the answer among `set`.
+///
+/// Guessing a candidate `w` finds the answer right away with probability `p(w)` and takes at
+/// least one more guess otherwise, while any other guess takes at least two.
+fn lower_bound(set: &[(usize, usize, f64)]) -> f64 {
+    if set.len() == 1 {
+        return 1.0;
+    }
+    let total: f64 = set.iter().map(|&(_, _, p)| p).sum();
+    let most_likely = set.iter().map(|&(_, _, p)| p).fold(0.0, f64::max);
+    2.0 - most_likely / total
+}
+
+impl<const N: usize> Search<'_, N> {
+    fn solve(&mut self, set: &[(usize, f64)], guesses_left: Option<usize>) -> (f64, usize) {
+        match (set, guesses_left) {
+            (_, Some(0)) => return (f64::INFINITY, set[0].0),
+            (&[(idx, _)], _) => return (1.0, idx),
+            (_, Some(1)) => return (f64::INFINITY, set[0].0),
+            _ => {}
+        }
+
+        let key = (set.iter().map(|&(idx, _)| idx).collect(), guesses_left);
+        if let Some(&best) = self.memo.get(&key) {
+            return best;
+        }
+
+        let mut splits = self.splits(set);
+        splits.sort_by(|a, b| a.bound.total_cmp(&b.bound));
+
+        let total: f64 = set.iter().map(|&(_, p)| p).sum();
+        let green = green_index::<N>();
+        let mut best = (f64::INFINITY, set[0].0);
+        let mut group = Vec::new();
+        for split in splits {
+            // The splits are sorted by their bounds, so none of the rest can do better.
+            if split.bound >= best.0 {
+                break;
+            }
+
+            // Swap each group's lower bound for its exact cost, giving up as soon as this guess
+            // can no longer beat the best one.
+            let mut cost = split.bound;
+            for patterns in split.patterns.chunk_by(|a, b| a.0 == b.0) {
+                if patterns[0].0 == green {
+                    continue;
+                }
+                group.clear();
+                group.extend(patterns.iter().map(|&(_, idx, p)| (idx, p)));
+                let weight = group.iter().map(|&(_, p)| p).sum::<f64>() / total;
+                let (exact, _) = self.solve(&group, guesses_left.map(|left| left - 1));
+                cost += weight * (exact - lower_bound(patterns));
+                if cost >= best.0 {
+                    break;
+                }
+            }
+            if cost < best.0 {
+                best = (cost, split.guess);
+            }
+        }
+
+        self.memo.insert(key, best);
+        best
+    }
+
+    /// Every guess worth considering for `set`, with its lower bound.
+    ///
+    /// Candidates come first, so that they win ties. Guesses that are not candidates are skipped
+    /// if they do not split `set` at all, or if they split it the same way as an earlier guess.
+    fn splits(&self, set: &[(usize, f64)]) -> Vec<Split> {
+        let dictionary = &self.options.dictionary;
+        let is_candidate: HashSet<usize> = set.iter().map(|&(idx, _)| idx).collect();
+        let others =
+            (0..dictionary.len()).filter(|idx| self.any_word && !is_candidate.contains(idx));
+
+        let total: f64 = set.iter().map(|&(_, p)| p).sum();
+        let green = green_index::<N>();
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        let mut signature = Vec::with_capacity(set.len());
+        let mut splits = Vec::new();
+        for guess in set.iter().map(|&(idx, _)| idx).chain(others) {
+            let mut patterns = Vec::with_capacity(set.len());
+            self.options
+                .for_each_pattern(guess, set, |&(idx, p), pattern| {
+                    patterns.push((pattern, idx, p))
+                });
+
+            if !is_candidate.contains(&guess) {
+                signature.clear();
+                signature.extend(patterns.iter().map(|&(pattern, _, _)| pattern));
+                if signature.iter().all(|&pattern| pattern == signature[0])
+                    || !seen.insert(signature.clone())
+                {
+                    continue;
+                }
+            }
+
+            patterns.sort_by_key(|&(pattern, _, _)| pattern);
+            let bound = 1.0
+                + patterns
+                    .chunk_by(|a, b| a.0 == b.0)
+                    .filter(|group| group[0].0 != green)
+                    .map(|group| {
+                        let weight = group.iter().map(|&(_, _, p)| p).sum::<f64>() / total;
+                        weight * lower_bound(group)
+                    })
+                    .sum::<f64>();
+            splits.push(Split {
+                guess,
+                bound,
+                patterns,
+            });
+        }
+        splits
+    }
+}
+
+/// The index of the pattern where every letter is correct.
+fn green_index<const N: usize>() -> usize {
+    Pattern([Correctness::Correct; N]).index()
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+/// An error returned when a game could not be played, or when a guess and its feedback do not make
+/// sense.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A word did not have `expected_len` lowercase ascii letters.
+    InvalidWord { word: String, expected_len: usize },
+
+    /// A guess was not one of the dictionary's words.
+    NotInDictionary(String),
+
+    /// The feedback for guess number `guess` marks `letter` yellow after a gray copy of it, which
+    /// the game never does.
+    ImpossiblePattern {
+        guess: usize,
+        word: String,
+        letter: char,
+    },
+
+    /// No word in the dictionary fits the feedback up to guess number `guess`.
+    ///
+    /// `contradicts` is the earlier guess whose feedback alone cannot be reconciled with that of
+    /// `guess`, if there is a single one.
+    Inconsistent {
+        guess: usize,
+        word: String,
+        contradicts: Option<usize>,
+    },
+
+    /// In hard mode, guess number `guess` ignored a hint revealed by an earlier guess.
+    HardMode {
+        guess: usize,
+        word: String,
+        violation: HardModeError,
+    },
+
+    /// Guess number `guess` of a game on several boards had feedback for `found` boards, rather
+    /// than for each of the `expected` boards.
+    Boards {
+        guess: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    /// Every board of a game on several boards is solved, so there is nothing left to guess.
+    Solved,
+
+    /// No word in the dictionary could be the answer, even before any guesses, so there is
+    /// nothing to guess.
+    Empty,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidWord { word, expected_len } => write!(
+                f,
+                "'{}' is not a word of {} lowercase ascii letters",
+                word, expected_len
+            ),
+            Self::NotInDictionary(word) => write!(f, "'{}' is not in the dictionary", word),
+            Self::ImpossiblePattern {
+                guess,
+                word,
+                letter,
+            } => write!(
+                f,
+                "the feedback for guess {} ('{}') marks {} yellow after a gray {}, which the game \
+                 never does",
+                guess,
+                word,
+                letter.to_ascii_uppercase(),
+                letter.to_ascii_uppercase()
+            ),
+            Self::Inconsistent {
+                guess: 1,
+                word,
+                contradicts: None,
+            } => write!(
+                f,
+                "no word in the dictionary fits the feedback for guess 1 ('{}')",
+                word
+            ),
+            Self::Inconsistent {
+                guess,
+                word,
+                contradicts: None,
+            } => write!(
+                f,
+                "the feedback for guess {} ('{}') contradicts the feedback for the earlier guesses",
+                guess, word
+            ),
+            Self::Inconsistent {
+                guess,
+                word,
+                contradicts: Some(earlier),
+            } => write!(
+                f,
+                "the feedback for guess {} ('{}') contradicts the feedback for guess {}",
+                guess, word, earlier
+            ),
+            Self::HardMode {
+                guess,
+                word,
+                violation,
+            } => write!(
+                f,
+                "guess {} ('{}') breaks hard mode: {}",
+                guess, word, violation
+            ),
+            Self::Boards {
+                guess,
+                expected,
+                found,
+            } => write!(
+                f,
+                "guess {} has feedback for {} boards rather than {}",
+                guess, found, expected
+            ),
+            Self::Solved => write!(f, "every board is solved already"),
+            Self::Empty => write!(f, "no word in the dictionary could be the answer"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::HardMode { violation, .. } => Some(violation),
+            _ => None,
+        }
+    }
+}
+
+/// A hint that a guess had to use in hard mode, but did not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HardModeError {
+    /// The letter at `position` (counting from 0) was green, and must stay in place.
+    Green { position: usize, letter: char },
+
+    /// `letter` was revealed `count` times, and must be used at least as often.
+    Yellow { letter: char, count: usize },
+}
+
+impl fmt::Display for HardModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Green { position, letter } => {
+                let n = position + 1;
+                let suffix = match (n % 10, n % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                write!(
+                    f,
+                    "{}{} letter must be {}",
+                    n,
+                    suffix,
+                    letter.to_ascii_uppercase()
+                )
+            }
+            Self::Yellow { letter, count: 1 } => {
+                write!(f, "guess must contain {}", letter.to_ascii_uppercase())
+            }
+            Self::Yellow { letter, count } => write!(
+                f,
+                "guess must contain {} {} times",
+                letter.to_ascii_uppercase(),
+                count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HardModeError {}
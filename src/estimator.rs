@@ -0,0 +1,267 @@
+use std::{fmt, io, path::Path};
+
+/// An estimate of how many _more_ guesses are needed to find the answer, given the entropy of the
+/// candidates that remain. [`Rank::ExpectedScore`](crate::Rank::ExpectedScore) and the lookahead
+/// use it for the guesses beyond those they search.
+///
+/// The default was fitted to the embedded dictionary. Other dictionaries and priors are better
+/// served by an estimator fitted to games played with them, see [`Estimator::fit`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Estimator {
+    /// `ln(entropy * scale + offset)`
+    Log { scale: f64, offset: f64 },
+
+    /// Straight lines between points of `(entropy, guesses)` in order of entropy, and the guesses
+    /// of the nearest point beyond them.
+    PiecewiseLinear(Vec<(f64, f64)>),
+}
+
+impl Default for Estimator {
+    fn default() -> Self {
+        // Constructed by iterative regression.
+        Self::Log {
+            scale: 3.870,
+            offset: 3.679,
+        }
+    }
+}
+
+/// The shape of the curve that [`Estimator::fit`] fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Model {
+    /// [`Estimator::Log`]
+    Log,
+
+    /// [`Estimator::PiecewiseLinear`], with a point for every bit of entropy
+    PiecewiseLinear,
+}
+
+/// An error returned when an [`Estimator`] could not be loaded.
+#[derive(Debug)]
+pub enum EstimatorError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// The profile was not a model name followed by its parameters.
+    Parse(String),
+}
+
+impl fmt::Display for EstimatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read estimator profile: {}", e),
+            Self::Parse(content) => write!(
+                f,
+                "the estimator profile should be `log <scale> <offset>` or `piecewise-linear` \
+                 followed by `<entropy> <guesses>` pairs, found '{}'",
+                content
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EstimatorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for EstimatorError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl Estimator {
+    /// The estimated number of guesses needed when `entropy` bits remain.
+    pub fn steps_left(&self, entropy: f64) -> f64 {
+        match self {
+            Self::Log { scale, offset } => (entropy * scale + offset).ln(),
+            Self::PiecewiseLinear(points) => {
+                let after = points.partition_point(|&(x, _)| x < entropy);
+                match (after.checked_sub(1).map(|i| points[i]), points.get(after)) {
+                    (Some((x0, y0)), Some(&(x1, y1))) => {
+                        y0 + (y1 - y0) * (entropy - x0) / (x1 - x0)
+                    }
+                    (Some((_, y)), None) | (None, Some(&(_, y))) => y,
+                    (None, None) => 0.0,
+                }
+            }
+        }
+    }
+
+    /// The fewest guesses estimated for any entropy between `low` and `high`.
+    pub fn min_steps_left(&self, low: f64, high: f64) -> f64 {
+        let ends = self.steps_left(low).min(self.steps_left(high));
+        match self {
+            // The logarithm is monotonic, so it is lowest at one of the ends.
+            Self::Log { .. } => ends,
+            Self::PiecewiseLinear(points) => points
+                .iter()
+                .filter(|&&(x, _)| low < x && x < high)
+                .fold(ends, |lowest, &(_, y)| lowest.min(y)),
+        }
+    }
+
+    /// Fit `model` to samples of the entropy that remained at some point in a game, and the number
+    /// of guesses that were still needed from there, such as those of [`Solver::samples`].
+    ///
+    /// Returns `None` if there are too few samples to fit the model.
+    ///
+    /// [`Solver::samples`]: crate::Solver::samples
+    pub fn fit(model: Model, samples: &[(f64, usize)]) -> Option<Self> {
+        match model {
+            Model::Log => fit_log(samples),
+            Model::PiecewiseLinear => fit_piecewise_linear(samples),
+        }
+    }
+
+    /// The root mean square error of this estimator over `samples`.
+    pub fn error(&self, samples: &[(f64, usize)]) -> f64 {
+        let sum: f64 = samples
+            .iter()
+            .map(|&(entropy, guesses)| (self.steps_left(entropy) - guesses as f64).powi(2))
+            .sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    /// Parse a profile in the format this estimator is displayed in: `log <scale> <offset>`, or
+    /// `piecewise-linear` followed by its points as `<entropy> <guesses>` pairs.
+    pub fn parse(text: &str) -> Result<Self, EstimatorError> {
+        let error = || EstimatorError::Parse(text.trim().to_string());
+        let mut parts = text.split_whitespace();
+        let model = parts.next().ok_or_else(error)?;
+        let numbers = parts
+            .map(str::parse)
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| error())?;
+        match (model, &*numbers) {
+            ("log", &[scale, offset]) => Ok(Self::Log { scale, offset }),
+            ("piecewise-linear", numbers) if !numbers.is_empty() && numbers.len() % 2 == 0 => {
+                let points: Vec<(f64, f64)> = numbers.chunks(2).map(|p| (p[0], p[1])).collect();
+                if points.windows(2).all(|w| w[0].0 < w[1].0) {
+                    Ok(Self::PiecewiseLinear(points))
+                } else {
+                    Err(error())
+                }
+            }
+            _ => Err(error()),
+        }
+    }
+
+    /// Load a profile in the format of [`Estimator::parse`] from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EstimatorError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Save this estimator to a file, in the format of [`Estimator::parse`].
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Estimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Log { scale, offset } => writeln!(f, "log {} {}", scale, offset),
+            Self::PiecewiseLinear(points) => {
+                writeln!(f, "piecewise-linear")?;
+                for (entropy, guesses) in points {
+                    writeln!(f, "{} {}", entropy, guesses)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Fit `ln(entropy * scale + offset)` by least squares, starting from a straight line through
+/// `e^guesses` and improving on it with Gauss-Newton steps.
+fn fit_log(samples: &[(f64, usize)]) -> Option<Estimator> {
+    let exp: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|&(entropy, guesses)| (entropy, (guesses as f64).exp()))
+        .collect();
+    let (scale, offset) = fit_line(&exp)?;
+    // The logarithm needs a positive argument for every entropy down to 0.
+    let (mut scale, mut offset) = (scale.max(0.0), if offset > 0.0 { offset } else { 1.0 });
+
+    for _ in 0..50 {
+        // The normal equations for the step, with the Jacobian of each residual.
+        let (mut jj, mut jr) = ([[0.0f64; 2]; 2], [0.0f64; 2]);
+        for &(entropy, guesses) in samples {
+            let inner = entropy * scale + offset;
+            if inner <= 0.0 {
+                return None;
+            }
+            let residual = guesses as f64 - inner.ln();
+            let j = [entropy / inner, 1.0 / inner];
+            for (row, &ji) in j.iter().enumerate() {
+                jr[row] += ji * residual;
+                for (col, &jk) in j.iter().enumerate() {
+                    jj[row][col] += ji * jk;
+                }
+            }
+        }
+        let det = jj[0][0] * jj[1][1] - jj[0][1] * jj[1][0];
+        if det.abs() < f64::EPSILON {
+            break;
+        }
+        let d_scale = (jj[1][1] * jr[0] - jj[0][1] * jr[1]) / det;
+        let d_offset = (jj[0][0] * jr[1] - jj[1][0] * jr[0]) / det;
+
+        // Halve the step until it keeps the logarithm defined.
+        let mut step = 1.0;
+        while offset + step * d_offset <= 0.0 || scale + step * d_scale < 0.0 {
+            step /= 2.0;
+        }
+        scale += step * d_scale;
+        offset += step * d_offset;
+        if (step * d_scale).abs() < 1e-9 && (step * d_offset).abs() < 1e-9 {
+            break;
+        }
+    }
+    Some(Estimator::Log { scale, offset })
+}
+
+/// The slope and intercept of the least squares line through `points`.
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|&(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points
+        .iter()
+        .map(|&(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    if points.len() < 2 || sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// A point at the mean entropy and guesses of the samples in each bit of entropy.
+fn fit_piecewise_linear(samples: &[(f64, usize)]) -> Option<Estimator> {
+    let mut bins: Vec<(f64, f64, usize)> = Vec::new();
+    for &(entropy, guesses) in samples {
+        let bin = entropy.max(0.0).floor() as usize;
+        if bins.len() <= bin {
+            bins.resize(bin + 1, (0.0, 0.0, 0));
+        }
+        bins[bin].0 += entropy;
+        bins[bin].1 += guesses as f64;
+        bins[bin].2 += 1;
+    }
+    let points: Vec<(f64, f64)> = bins
+        .into_iter()
+        .filter(|&(_, _, n)| n > 0)
+        .map(|(entropy, guesses, n)| (entropy / n as f64, guesses / n as f64))
+        .collect();
+    (!points.is_empty()).then_some(Estimator::PiecewiseLinear(points))
+}
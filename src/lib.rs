@@ -1,42 +1,119 @@
-use std::{borrow::Cow, collections::HashSet, num::NonZeroU8};
+use std::{
+    borrow::Cow,
+    num::{NonZeroU16, NonZeroU8},
+    sync::Arc,
+};
 
+mod bitset;
+mod book;
+mod dictionary;
+mod endgame;
+mod error;
+mod estimator;
+mod matrix;
+mod multi;
+mod packed;
+mod pattern;
+mod patterns;
+mod prior;
 mod solver;
-pub use solver::{Rank, Solver};
+pub use book::{Book, BookError};
+pub use dictionary::{Dictionary, DictionaryError};
+pub use error::{Error, HardModeError};
+pub use estimator::{Estimator, EstimatorError, Model};
+pub use matrix::{Matrix, MatrixError};
+pub use multi::MultiSolver;
+pub use packed::PackedWords;
+pub use pattern::{ParsePatternError, Pattern};
+pub use prior::Prior;
+pub use solver::{HardMode, Opener, Options, Rank, Solver};
 
 include!(concat!(env!("OUT_DIR"), "/dictionary.rs"));
 
-pub struct Wordle {
-    dictionary: HashSet<&'static str>,
+/// A game of Wordle with `N` letter words.
+pub struct Wordle<const N: usize = 5> {
+    dictionary: Arc<Dictionary<N>>,
+    hard_mode: bool,
 }
 
 impl Default for Wordle {
     fn default() -> Self {
-        Self::new()
+        Self::with_dictionary(Dictionary::embedded())
     }
 }
 
 impl Wordle {
+    /// A game of Wordle with the embedded five letter words. Other lengths need a dictionary of
+    /// their own, from [`Dictionary::load`] or [`Dictionary::from_words`], to play with
+    /// [`Wordle::with_dictionary`].
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<const N: usize> Wordle<N> {
+    /// A game of Wordle where only the words of `dictionary` may be guessed.
+    pub fn with_dictionary(dictionary: Arc<Dictionary<N>>) -> Self {
         Self {
-            dictionary: HashSet::from_iter(DICTIONARY.iter().copied().map(|(word, _)| word)),
+            dictionary,
+            hard_mode: false,
         }
     }
 
-    pub fn play<G: Guesser>(&self, answer: &'static str, mut guesser: G) -> Option<usize> {
+    /// Enforce the rules of hard mode, where every guess must use the hints revealed so far: green
+    /// letters stay in place, and yellow letters are used again.
+    pub fn with_hard_mode(mut self, hard_mode: bool) -> Self {
+        self.hard_mode = hard_mode;
+        self
+    }
+
+    pub fn dictionary(&self) -> &Arc<Dictionary<N>> {
+        &self.dictionary
+    }
+
+    /// Play a game, panicking if the guesser breaks the rules. See [`Wordle::try_play`].
+    pub fn play<G: Guesser<N>>(&self, answer: &str, guesser: G) -> Option<usize> {
+        self.try_play(answer, guesser)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Play a game, returning the number of guesses it took, or `None` if the answer was not
+    /// found within 32 guesses.
+    ///
+    /// Fails if the answer or a guess is not in the dictionary, if the guesser fails, or if a guess
+    /// breaks the rules of hard mode when that is enforced.
+    pub fn try_play<G: Guesser<N>>(
+        &self,
+        answer: &str,
+        mut guesser: G,
+    ) -> Result<Option<usize>, Error> {
+        check_word::<N>(answer)?;
+        if !self.dictionary.contains(answer) {
+            return Err(Error::NotInDictionary(answer.to_string()));
+        }
         let mut history = Vec::new();
 
         // Popoki allows at least 32 guesses.
         for i in 1..=32 {
-            let guess = guesser.guess(&history);
+            let guess = guesser.try_guess(&history)?;
             if guess == answer {
                 guesser.finish(i);
-                return Some(i);
+                return Ok(Some(i));
+            }
+            if !self.dictionary.contains(&guess) {
+                return Err(Error::NotInDictionary(guess));
+            }
+            if self.hard_mode {
+                for previous in &history {
+                    if let Err(violation) = previous.check_hard_mode(&guess)? {
+                        return Err(Error::HardMode {
+                            guess: i,
+                            word: guess,
+                            violation,
+                        });
+                    }
+                }
             }
-            assert!(
-                self.dictionary.contains(&*guess),
-                "guess '{}' is not in the dictionary",
-                guess
-            );
 
             let correctness = Correctness::compute(answer, &guess);
             history.push(Guess {
@@ -44,7 +121,76 @@ impl Wordle {
                 mask: correctness,
             });
         }
-        None
+        Ok(None)
+    }
+
+    /// Check that `history` could have come from a game played with this dictionary: every guess is
+    /// in the dictionary, every pattern is one the game could show, and some word fits all of the
+    /// feedback.
+    pub fn validate(&self, history: &[Guess<'_, N>]) -> Result<(), Error> {
+        let mut remaining: Vec<&str> = self.dictionary.words().map(|(word, _)| word).collect();
+        for (i, guess) in history.iter().enumerate() {
+            guess.check(&self.dictionary, i + 1)?;
+            remaining.retain(|word| guess.matches(word));
+            if remaining.is_empty() {
+                return Err(inconsistent(&self.dictionary, &history[..=i]));
+            }
+        }
+        Ok(())
+    }
+
+    /// Play a game on several boards, panicking if the guesser breaks the rules. See
+    /// [`Wordle::try_play_boards`].
+    pub fn play_boards<G: MultiGuesser<N>>(&self, answers: &[&str], guesser: G) -> Option<usize> {
+        self.try_play_boards(answers, guesser)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Play a game on several boards at once, as in Dordle, Quordle or Octordle, where every guess
+    /// is scored against each of `answers`.
+    ///
+    /// Returns the number of guesses it took to solve every board, or `None` if they were not all
+    /// solved within 32 guesses. Guesses are made with [`MultiGuesser::try_guess`], and this fails
+    /// if that does, or if a guess is not in the dictionary.
+    pub fn try_play_boards<G: MultiGuesser<N>>(
+        &self,
+        answers: &[&str],
+        mut guesser: G,
+    ) -> Result<Option<usize>, Error> {
+        for answer in answers {
+            check_word::<N>(answer)?;
+        }
+        let mut history = Vec::new();
+        let mut solved = vec![false; answers.len()];
+
+        // Popoki allows at least 32 guesses.
+        for i in 1..=32 {
+            let guess = guesser.try_guess(&history)?;
+            if !self.dictionary.contains(&guess) {
+                return Err(Error::NotInDictionary(guess));
+            }
+
+            let masks = answers
+                .iter()
+                .zip(&mut solved)
+                .map(|(&answer, solved)| {
+                    if *solved {
+                        return None;
+                    }
+                    *solved = guess == answer;
+                    Some(Correctness::compute(answer, &guess))
+                })
+                .collect();
+            if solved.iter().all(|&solved| solved) {
+                guesser.finish(i);
+                return Ok(Some(i));
+            }
+            history.push(MultiGuess {
+                word: Cow::Owned(guess),
+                masks,
+            });
+        }
+        Ok(None)
     }
 }
 
@@ -58,8 +204,44 @@ pub enum Correctness {
     Wrong,
 }
 
+/// Check that `word` has exactly `N` lowercase ascii letters.
+fn check_word<const N: usize>(word: &str) -> Result<(), Error> {
+    if word.len() == N && word.bytes().all(|b| b.is_ascii_lowercase()) {
+        Ok(())
+    } else {
+        Err(Error::InvalidWord {
+            word: word.to_string(),
+            expected_len: N,
+        })
+    }
+}
+
+/// The error for the last guess of `history`, whose feedback leaves no word in `dictionary` that
+/// fits all of it.
+fn inconsistent<const N: usize>(dictionary: &Dictionary<N>, history: &[Guess<'_, N>]) -> Error {
+    let (last, earlier) = history.split_last().expect("history has a guess");
+    let fits = |guesses: &[&Guess<'_, N>]| {
+        dictionary
+            .words()
+            .any(|(word, _)| guesses.iter().all(|guess| guess.matches(word)))
+    };
+    let contradicts = if fits(&[last]) {
+        earlier
+            .iter()
+            .position(|guess| !fits(&[guess, last]))
+            .map(|i| i + 1)
+    } else {
+        None
+    };
+    Error::Inconsistent {
+        guess: history.len(),
+        word: last.word.to_string(),
+        contradicts,
+    }
+}
+
 impl Correctness {
-    fn is_misplaced(letter: u8, answer: &str, used: &mut [bool; 5]) -> bool {
+    fn is_misplaced<const N: usize>(letter: u8, answer: &str, used: &mut [bool; N]) -> bool {
         answer.bytes().enumerate().any(|(i, a)| {
             if a == letter && !used[i] {
                 used[i] = true;
@@ -69,14 +251,14 @@ impl Correctness {
         })
     }
 
-    /// Given an answer and a guess, return an array of 5 elements, each of which is
+    /// Given an answer and a guess, return an array of `N` elements, each of which is
     /// a `Result` indicating whether the guess is correct, incorrect, or not present
-    pub fn compute(answer: &str, guess: &str) -> [Self; 5] {
+    pub fn compute<const N: usize>(answer: &str, guess: &str) -> [Self; N] {
         // Sanity check for `answer` and `guess`
-        assert_eq!(answer.len(), 5);
-        assert_eq!(guess.len(), 5);
+        assert_eq!(answer.len(), N);
+        assert_eq!(guess.len(), N);
 
-        let mut c = [Correctness::Wrong; 5];
+        let mut c = [Correctness::Wrong; N];
         let answer_bytes = answer.as_bytes();
         let guess_bytes = guess.as_bytes();
 
@@ -103,28 +285,33 @@ impl Correctness {
         }
         c
     }
-}
 
-pub const MAX_MASK_ENUM: usize = 3 * 3 * 3 * 3 * 3;
+    /// Like [`Correctness::compute`], but fails instead of panicking if either word does not
+    /// have `N` lowercase ascii letters.
+    pub fn try_compute<const N: usize>(answer: &str, guess: &str) -> Result<[Self; N], Error> {
+        check_word::<N>(answer)?;
+        check_word::<N>(guess)?;
+        Ok(Self::compute(answer, guess))
+    }
+}
 
-/// A wrapper type for `[Correctness; 5]` packed into a single byte with a niche.
+/// A wrapper type for `[Correctness; N]` packed into a single byte with a niche.
+///
+/// This only fits patterns of up to 5 letters. Longer words use [`WidePackedCorrectness`].
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(transparent)]
 // The NonZeroUB here lets the compiler know that we're not using the value `0`,
 // and that `0` can therefore be used to represent `None` for `Option<PackedCorrectness>`
 pub struct PackedCorrectness(NonZeroU8);
 
-impl From<[Correctness; 5]> for PackedCorrectness {
-    fn from(c: [Correctness; 5]) -> Self {
-        let packed = c.iter().fold(0, |acc, c| {
-            acc * 3
-                + match c {
-                    Correctness::Correct => 0,
-                    Correctness::Misplaced => 1,
-                    Correctness::Wrong => 2,
-                }
-        });
-        Self(NonZeroU8::new(packed + 1).unwrap())
+impl<const N: usize> From<[Correctness; N]> for PackedCorrectness {
+    fn from(c: [Correctness; N]) -> Self {
+        assert!(
+            Pattern::<N>::COUNT < usize::from(u8::MAX),
+            "{} letter patterns do not fit in a byte",
+            N
+        );
+        Self(NonZeroU8::new(Pattern(c).index() as u8 + 1).unwrap())
     }
 }
 
@@ -134,17 +321,41 @@ impl From<PackedCorrectness> for u8 {
     }
 }
 
-pub struct Guess<'a> {
+/// A wrapper type for `[Correctness; N]` packed into two bytes with a niche.
+///
+/// This fits patterns of up to 10 letters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct WidePackedCorrectness(NonZeroU16);
+
+impl<const N: usize> From<[Correctness; N]> for WidePackedCorrectness {
+    fn from(c: [Correctness; N]) -> Self {
+        assert!(
+            Pattern::<N>::COUNT < usize::from(u16::MAX),
+            "{} letter patterns do not fit in two bytes",
+            N
+        );
+        Self(NonZeroU16::new(Pattern(c).index() as u16 + 1).unwrap())
+    }
+}
+
+impl From<WidePackedCorrectness> for u16 {
+    fn from(this: WidePackedCorrectness) -> Self {
+        this.0.get() - 1
+    }
+}
+
+pub struct Guess<'a, const N: usize = 5> {
     pub word: Cow<'a, str>,
-    pub mask: [Correctness; 5],
+    pub mask: [Correctness; N],
 }
 
-impl Guess<'_> {
+impl<const N: usize> Guess<'_, N> {
     pub fn matches(&self, word: &str) -> bool {
-        assert_eq!(word.len(), 5);
-        assert_eq!(self.word.len(), 5);
+        assert_eq!(word.len(), N);
+        assert_eq!(self.word.len(), N);
 
-        let mut used = [false; 5];
+        let mut used = [false; N];
 
         // Check correct letters
         for (i, (a, g)) in word.bytes().zip(self.word.bytes()).enumerate() {
@@ -171,15 +382,155 @@ impl Guess<'_> {
         // The rest will be all correctly wrong letters
         true
     }
+
+    /// Like [`Guess::matches`], but fails instead of panicking if either word does not have `N`
+    /// lowercase ascii letters.
+    pub fn try_matches(&self, word: &str) -> Result<bool, Error> {
+        check_word::<N>(word)?;
+        check_word::<N>(&self.word)?;
+        Ok(self.matches(word))
+    }
+
+    /// Check that this guess, played as guess number `number`, is in `dictionary` and has
+    /// feedback that the game could show.
+    fn check(&self, dictionary: &Dictionary<N>, number: usize) -> Result<(), Error> {
+        check_word::<N>(&self.word)?;
+        if !dictionary.contains(&self.word) {
+            return Err(Error::NotInDictionary(self.word.to_string()));
+        }
+
+        // Copies of a letter are marked yellow from left to right, so a yellow never follows a
+        // gray copy of the same letter.
+        for (i, (g, c)) in self.word.bytes().zip(self.mask).enumerate() {
+            let gray_before = self
+                .word
+                .bytes()
+                .zip(self.mask)
+                .take(i)
+                .any(|(l, c)| l == g && c == Correctness::Wrong);
+            if c == Correctness::Misplaced && gray_before {
+                return Err(Error::ImpossiblePattern {
+                    guess: number,
+                    word: self.word.to_string(),
+                    letter: char::from(g),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `word` uses the hints this guess revealed, as hard mode requires: every green
+    /// letter stays in place, and every revealed letter is used at least as often as it was
+    /// revealed.
+    ///
+    /// Unlike [`Guess::matches`], this allows words that were already ruled out as the answer.
+    ///
+    /// The outer result fails if either word does not have `N` lowercase ascii letters, and the
+    /// inner one holds the first hint that `word` ignores.
+    pub fn check_hard_mode(&self, word: &str) -> Result<Result<(), HardModeError>, Error> {
+        check_word::<N>(word)?;
+        check_word::<N>(&self.word)?;
+
+        for (position, ((g, w), c)) in self
+            .word
+            .bytes()
+            .zip(word.bytes())
+            .zip(self.mask)
+            .enumerate()
+        {
+            if c == Correctness::Correct && g != w {
+                return Ok(Err(HardModeError::Green {
+                    position,
+                    letter: char::from(g),
+                }));
+            }
+        }
+
+        // Count how often each letter was revealed, less the times that `word` uses it.
+        let mut missing = [0usize; (b'z' - b'a' + 1) as usize];
+        for (g, c) in self.word.bytes().zip(self.mask) {
+            if c != Correctness::Wrong {
+                missing[usize::from(g - b'a')] += 1;
+            }
+        }
+        for w in word.bytes() {
+            let missing = &mut missing[usize::from(w - b'a')];
+            *missing = missing.saturating_sub(1);
+        }
+
+        // Report the letters in the order they were guessed.
+        for (g, c) in self.word.bytes().zip(self.mask) {
+            if c != Correctness::Wrong && missing[usize::from(g - b'a')] > 0 {
+                let count = self
+                    .word
+                    .bytes()
+                    .zip(self.mask)
+                    .filter(|&(l, c)| l == g && c != Correctness::Wrong)
+                    .count();
+                return Ok(Err(HardModeError::Yellow {
+                    letter: char::from(g),
+                    count,
+                }));
+            }
+        }
+        Ok(Ok(()))
+    }
 }
 
-pub trait Guesser {
-    fn guess(&mut self, history: &[Guess]) -> String;
+pub trait Guesser<const N: usize = 5> {
+    fn guess(&mut self, history: &[Guess<'_, N>]) -> String;
+
+    /// Like [`Guesser::guess`], but fails instead of panicking if `history` does not make sense.
+    /// Guessers that never fail need not implement this.
+    fn try_guess(&mut self, history: &[Guess<'_, N>]) -> Result<String, Error> {
+        Ok(self.guess(history))
+    }
+
     fn finish(&self, _guesses: usize) {}
 }
 
-impl Guesser for fn(history: &[Guess]) -> String {
-    fn guess(&mut self, history: &[Guess]) -> String {
+/// Lets a guesser be played without giving it up, so that it can be inspected after the game.
+impl<G: Guesser<N> + ?Sized, const N: usize> Guesser<N> for &mut G {
+    fn guess(&mut self, history: &[Guess<'_, N>]) -> String {
+        (**self).guess(history)
+    }
+
+    fn try_guess(&mut self, history: &[Guess<'_, N>]) -> Result<String, Error> {
+        (**self).try_guess(history)
+    }
+
+    fn finish(&self, guesses: usize) {
+        (**self).finish(guesses);
+    }
+}
+
+impl<const N: usize> Guesser<N> for fn(history: &[Guess<'_, N>]) -> String {
+    fn guess(&mut self, history: &[Guess<'_, N>]) -> String {
+        (*self)(history)
+    }
+}
+
+/// A guess in a game played on several boards at once, with the feedback of every board.
+pub struct MultiGuess<'a, const N: usize = 5> {
+    pub word: Cow<'a, str>,
+    /// The feedback of each board, or `None` for the boards that an earlier guess solved.
+    pub masks: Vec<Option<[Correctness; N]>>,
+}
+
+pub trait MultiGuesser<const N: usize = 5> {
+    fn guess(&mut self, history: &[MultiGuess<'_, N>]) -> String;
+
+    /// Like [`MultiGuesser::guess`], but fails instead of panicking if `history` does not make
+    /// sense. Guessers that never fail need not implement this.
+    fn try_guess(&mut self, history: &[MultiGuess<'_, N>]) -> Result<String, Error> {
+        Ok(self.guess(history))
+    }
+
+    fn finish(&self, _guesses: usize) {}
+}
+
+impl<const N: usize> MultiGuesser<N> for fn(history: &[MultiGuess<'_, N>]) -> String {
+    fn guess(&mut self, history: &[MultiGuess<'_, N>]) -> String {
         (*self)(history)
     }
 }
@@ -208,6 +559,21 @@ macro_rules! mask {
 
 #[cfg(test)]
 mod tests {
+    use crate::{Dictionary, Options};
+    use std::sync::Arc;
+
+    /// Options for `take` words of the embedded dictionary after the first `skip`, which are quick
+    /// to search.
+    fn small_options(skip: usize, take: usize) -> Options {
+        let words = Dictionary::embedded()
+            .words()
+            .skip(skip)
+            .take(take)
+            .map(|(word, count)| (word.to_string(), count))
+            .collect::<Vec<_>>();
+        Options::with_dictionary(Arc::new(Dictionary::from_words(words).unwrap()))
+    }
+
     mod guess_matcher {
         use crate::Guess;
         use std::borrow::Cow;
@@ -320,6 +686,1358 @@ mod tests {
             assert_eq!(w.play("right", guesser), None);
         }
     }
+    mod hard_mode {
+        use crate::{Error, Guess, HardMode, HardModeError, Options, Wordle};
+        use std::borrow::Cow;
+
+        fn guess(word: &'static str, mask: [crate::Correctness; 5]) -> Guess<'static> {
+            Guess {
+                word: Cow::Borrowed(word),
+                mask,
+            }
+        }
+
+        #[test]
+        fn greens_stay() {
+            let g = guess("crane", mask![W C W W W]);
+            assert_eq!(g.check_hard_mode("front"), Ok(Ok(())));
+            assert_eq!(g.check_hard_mode("crane"), Ok(Ok(())));
+            assert_eq!(
+                g.check_hard_mode("tiger"),
+                Ok(Err(HardModeError::Green {
+                    position: 1,
+                    letter: 'r'
+                }))
+            );
+        }
+
+        #[test]
+        fn short_words() {
+            let g = guess("crane", mask![W C W W W]);
+            assert_eq!(
+                g.check_hard_mode("cra"),
+                Err(Error::InvalidWord {
+                    word: "cra".to_string(),
+                    expected_len: 5
+                })
+            );
+        }
+
+        #[test]
+        fn yellows_are_used() {
+            let g = guess("crane", mask![W M W W W]);
+            // Already ruled out as the answer, but allowed in hard mode.
+            assert_eq!(g.check_hard_mode("tiger"), Ok(Ok(())));
+            assert_eq!(
+                g.check_hard_mode("stink"),
+                Ok(Err(HardModeError::Yellow {
+                    letter: 'r',
+                    count: 1
+                }))
+            );
+
+            let g = guess("error", mask![W M M W W]);
+            assert_eq!(g.check_hard_mode("rarer"), Ok(Ok(())));
+            assert_eq!(
+                g.check_hard_mode("rupee"),
+                Ok(Err(HardModeError::Yellow {
+                    letter: 'r',
+                    count: 2
+                }))
+            );
+        }
+
+        #[test]
+        fn messages() {
+            let green = HardModeError::Green {
+                position: 3,
+                letter: 'r',
+            };
+            assert_eq!(green.to_string(), "4th letter must be R");
+            let yellow = HardModeError::Yellow {
+                letter: 'a',
+                count: 1,
+            };
+            assert_eq!(yellow.to_string(), "guess must contain A");
+        }
+
+        #[test]
+        fn enforced() {
+            let guesser = guesser!(|history| {
+                if history.is_empty() {
+                    return "crane".to_string();
+                }
+                "stink".to_string()
+            });
+            let w = Wordle::new().with_hard_mode(true);
+            assert_eq!(
+                w.try_play("right", guesser),
+                Err(Error::HardMode {
+                    guess: 2,
+                    word: "stink".to_string(),
+                    violation: HardModeError::Yellow {
+                        letter: 'r',
+                        count: 1
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn not_enforced() {
+            let guesser = guesser!(|history| {
+                if history.len() == 2 {
+                    return "right".to_string();
+                }
+                ["crane", "stink"][history.len()].to_string()
+            });
+            assert_eq!(Wordle::new().try_play("right", guesser), Ok(Some(3)));
+        }
+
+        #[test]
+        fn solver_follows_rules() {
+            let w = Wordle::new().with_hard_mode(true);
+            for answer in ["rebut", "awake", "focal"] {
+                for hard_mode in [HardMode::Hard, HardMode::Strict] {
+                    let options = Options {
+                        hard_mode,
+                        ..Options::default()
+                    };
+                    assert!(matches!(w.try_play(answer, options.build()), Ok(Some(_))));
+                }
+            }
+        }
+
+        #[test]
+        fn not_in_dictionary() {
+            let guesser = guesser!(|_history| { "xyzzy".to_string() });
+            assert_eq!(
+                Wordle::new().try_play("right", guesser),
+                Err(Error::NotInDictionary("xyzzy".to_string()))
+            );
+        }
+    }
+    mod errors {
+        use crate::{Correctness, Error, Guess, Guesser, Solver, Wordle};
+        use std::borrow::Cow;
+
+        fn guess(word: &'static str, mask: [Correctness; 5]) -> Guess<'static> {
+            Guess {
+                word: Cow::Borrowed(word),
+                mask,
+            }
+        }
+
+        #[test]
+        fn invalid_words() {
+            let invalid = Error::InvalidWord {
+                word: "abc".to_string(),
+                expected_len: 5,
+            };
+            assert_eq!(
+                Correctness::try_compute::<5>("abc", "crane"),
+                Err(invalid.clone())
+            );
+            assert_eq!(
+                guess("crane", mask![W W W W W]).try_matches("abc"),
+                Err(invalid)
+            );
+            assert_eq!(
+                Correctness::try_compute::<5>("cigar", "CRANE"),
+                Err(Error::InvalidWord {
+                    word: "CRANE".to_string(),
+                    expected_len: 5
+                })
+            );
+            assert_eq!(
+                Correctness::try_compute("cigar", "crane"),
+                Ok(mask![C M M W W])
+            );
+        }
+
+        #[test]
+        fn impossible_pattern() {
+            let history = [guess("error", mask![W W W W M])];
+            assert_eq!(
+                Wordle::new().validate(&history),
+                Err(Error::ImpossiblePattern {
+                    guess: 1,
+                    word: "error".to_string(),
+                    letter: 'r'
+                })
+            );
+        }
+
+        #[test]
+        fn inconsistent() {
+            let history = [
+                guess("trace", mask![W W W W W]),
+                guess("slate", mask![C W W W W]),
+                guess("crane", mask![W W W W M]),
+            ];
+            let expected = Error::Inconsistent {
+                guess: 3,
+                word: "crane".to_string(),
+                contradicts: Some(1),
+            };
+            assert_eq!(Wordle::new().validate(&history), Err(expected.clone()));
+            assert_eq!(
+                expected.to_string(),
+                "the feedback for guess 3 ('crane') contradicts the feedback for guess 1"
+            );
+        }
+
+        #[test]
+        fn solver_recovers() {
+            let mut solver = Solver::default();
+            let mut history = vec![guess("trace", mask![W W W W W])];
+            solver.guess(&[]);
+            solver.guess(&history);
+            let candidates = solver.candidates().count();
+
+            history.push(guess("xyzzy", mask![W W W W W]));
+            assert_eq!(
+                solver.try_guess(&history),
+                Err(Error::NotInDictionary("xyzzy".to_string()))
+            );
+            history.pop();
+            history.push(guess("crane", mask![C W W W W]));
+            assert!(matches!(
+                solver.try_guess(&history),
+                Err(Error::Inconsistent {
+                    guess: 2,
+                    contradicts: Some(1),
+                    ..
+                })
+            ));
+            assert_eq!(solver.candidates().count(), candidates);
+        }
+
+        #[test]
+        fn unknown_answer() {
+            assert_eq!(
+                Wordle::new().try_play("zzzzz", Solver::default()),
+                Err(Error::NotInDictionary("zzzzz".to_string()))
+            );
+        }
+
+        #[test]
+        fn unsatisfiable() {
+            let history = [guess("right", mask![C C C C M])];
+            assert_eq!(
+                Wordle::new().validate(&history),
+                Err(Error::Inconsistent {
+                    guess: 1,
+                    word: "right".to_string(),
+                    contradicts: None
+                })
+            );
+        }
+    }
+    mod history {
+        use crate::{Correctness, Guess, Guesser, Solver};
+        use std::borrow::Cow;
+
+        fn play(answer: &str, words: &[&'static str]) -> Vec<Guess<'static>> {
+            words
+                .iter()
+                .map(|&word| Guess {
+                    word: Cow::Borrowed(word),
+                    mask: Correctness::compute(answer, word),
+                })
+                .collect()
+        }
+
+        #[test]
+        fn picks_up_midway() {
+            let history = play("cigar", &["crane", "pilot"]);
+            let mut following = Solver::default();
+            following.guess(&[]);
+            following.guess(&history[..1]);
+            let expected = following.guess(&history);
+
+            let mut joining = Solver::default();
+            assert_eq!(joining.guess(&history), expected);
+            assert!(joining.candidates().eq(following.candidates()));
+        }
+
+        #[test]
+        fn starts_over() {
+            let mut solver = Solver::default();
+            let fresh = Solver::default().guess(&play("rebut", &["crane"]));
+            solver.guess(&play("cigar", &["crane", "pilot"]));
+            assert_eq!(solver.guess(&play("rebut", &["crane"])), fresh);
+            assert_eq!(solver.guess(&[]), "trace");
+            assert_eq!(
+                solver.candidates().count(),
+                Solver::builder().build().candidates().count()
+            );
+        }
+    }
+    mod rank {
+        use crate::{
+            Correctness, Dictionary, Guesser, HardMode, Options, Pattern, Prior, Rank, Wordle,
+        };
+        use std::{collections::HashMap, sync::Arc};
+
+        /// Words that only differ in their first letter, which are the answers.
+        const ILLS: [&str; 6] = ["bills", "fills", "hills", "mills", "pills", "wills"];
+
+        /// The answers, and two words that are not: one that tells some of them apart, and one
+        /// that tells them all apart.
+        const WORDS: [&str; 8] = [
+            "bills", "fills", "hills", "mills", "pills", "wills", "whelp", "bhfmw",
+        ];
+
+        /// Every word of `words` ranked by `rank_by` when `answers` are equally likely and any
+        /// word may be guessed.
+        fn ranked(rank_by: Rank, words: &[&str], answers: &[&str]) -> Vec<String> {
+            let dictionary = Dictionary::<5>::parse(&words.join("\n")).unwrap();
+            let options = Options {
+                dictionary: Arc::new(dictionary.with_answers(answers).unwrap()),
+                prior: Prior::Answers,
+                hard_mode: HardMode::Easy,
+                rank_by,
+                ..Options::default()
+            };
+            let mut solver = options.build();
+            let ranked = solver.rank_guesses(&[]).unwrap();
+            ranked.into_iter().map(|(word, _)| word).collect()
+        }
+
+        /// How many of `answers` get each pattern from `guess`, other than the one that solves the
+        /// game.
+        fn buckets(guess: &str, answers: &[&str]) -> Vec<usize> {
+            let solved = Pattern([Correctness::Correct; 5]).index();
+            let mut buckets = HashMap::new();
+            for answer in answers {
+                let pattern = Pattern(Correctness::compute::<5>(answer, guess)).index();
+                *buckets.entry(pattern).or_insert(0) += 1;
+            }
+            buckets.remove(&solved);
+            buckets.into_values().collect()
+        }
+
+        fn solves(rank_by: Rank) {
+            let w = Wordle::new();
+            let options: Options = Options {
+                rank_by,
+                ..Options::default()
+            };
+            for answer in ["cigar", "rebut", "sissy", "humph"] {
+                assert!(w.play(answer, options.clone().build()).is_some());
+            }
+        }
+
+        #[test]
+        fn minimax() {
+            solves(Rank::Minimax);
+        }
+
+        #[test]
+        fn minimax_smallest_bucket() {
+            let largest = |guess: &str| buckets(guess, &ILLS).into_iter().max().unwrap_or(0);
+            let ranked = ranked(Rank::Minimax, &WORDS, &ILLS);
+            assert_eq!(ranked[0], "bhfmw");
+            assert_eq!(largest(&ranked[0]), 1);
+            let smallest = WORDS.iter().map(|word| largest(word)).min();
+            assert_eq!(smallest, Some(largest(&ranked[0])));
+            // Each answer lumps the other five together, where `whelp` leaves three at most.
+            assert_eq!((largest("whelp"), largest("bills")), (3, 5));
+            assert_eq!(ranked[1], "whelp");
+        }
+
+        #[test]
+        fn minimax_prefers_candidates() {
+            // Every guess leaves at most one of the two answers, but `bzzzz` cannot be the answer,
+            // so it goes last even though it is considered first.
+            let words = ["bzzzz", "boats", "coats"];
+            assert_eq!(
+                ranked(Rank::Minimax, &words, &words[1..]),
+                ["boats", "coats", "bzzzz"]
+            );
+        }
+
+        #[test]
+        fn most_parts() {
+            solves(Rank::MostParts);
+        }
+
+        #[test]
+        fn most_parts_most_patterns() {
+            let parts = |guess: &str| {
+                let solves = ILLS.contains(&guess);
+                buckets(guess, &ILLS).len() + usize::from(solves)
+            };
+            let ranked = ranked(Rank::MostParts, &WORDS, &ILLS);
+            assert_eq!(ranked[0], "bhfmw");
+            assert_eq!(parts(&ranked[0]), 6);
+            let most = WORDS.iter().map(|word| parts(word)).max();
+            assert_eq!(most, Some(parts(&ranked[0])));
+            // `whelp` gets four patterns, while each answer gets two: itself and the rest.
+            assert_eq!((parts("whelp"), parts("bills")), (4, 2));
+            assert_eq!(ranked[1], "whelp");
+        }
+
+        #[test]
+        fn most_parts_prefers_candidates() {
+            // Every guess splits the two answers apart, but `bzzzz` cannot be the answer.
+            let words = ["bzzzz", "boats", "coats"];
+            assert_eq!(
+                ranked(Rank::MostParts, &words, &words[1..]),
+                ["boats", "coats", "bzzzz"]
+            );
+        }
+
+        #[test]
+        fn adaptive_cutoff() {
+            let w = Wordle::new();
+            for rank_by in [
+                Rank::ExpectedScore,
+                Rank::ExpectedInformation,
+                Rank::Minimax,
+                Rank::MostParts,
+            ] {
+                let exhaustive: Options = Options {
+                    rank_by,
+                    cutoff: false,
+                    ..Options::default()
+                };
+                let adaptive = Options {
+                    adaptive_cutoff: true,
+                    ..exhaustive.clone()
+                };
+                for answer in ["cigar", "rebut", "sissy"] {
+                    let mut a = exhaustive.clone().build();
+                    let mut b = adaptive.clone().build();
+                    assert_eq!(w.play(answer, &mut a), w.play(answer, &mut b));
+                    assert_eq!(
+                        a.candidates().collect::<Vec<_>>(),
+                        b.candidates().collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn time_budget() {
+            let w = Wordle::new();
+            let options: Options = Options {
+                time_budget: Some(std::time::Duration::ZERO),
+                ..Options::default()
+            };
+            for answer in ["cigar", "rebut", "sissy", "humph"] {
+                let mut solver = options.clone().build();
+                assert!(w.play(answer, &mut solver).is_some());
+                assert!(solver.evaluated().iter().all(|&evaluated| evaluated == 1));
+            }
+
+            let mut solver = Options::default().build();
+            w.play("cigar", &mut solver);
+            assert!(solver.evaluated().iter().all(|&evaluated| evaluated > 1));
+        }
+
+        #[test]
+        fn time_budget_any_word() {
+            let w = Wordle::new();
+            for hard_mode in [HardMode::Easy, HardMode::Hard] {
+                for rank_by in [
+                    Rank::WeightedInformation,
+                    Rank::InfoPlusProbability,
+                    Rank::ExpectedInformation,
+                ] {
+                    let options: Options = Options {
+                        rank_by,
+                        hard_mode,
+                        time_budget: Some(std::time::Duration::ZERO),
+                        ..Options::default()
+                    };
+                    for answer in ["cigar", "rebut", "sissy", "humph", "awake"] {
+                        assert!(w.play(answer, options.clone().build()).is_some());
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn lookahead() {
+            let w = Wordle::new();
+            let options: Options = Options {
+                lookahead_depth: 2,
+                lookahead_width: 3,
+                ..Options::default()
+            };
+            for answer in ["cigar", "rebut", "sissy", "humph"] {
+                assert!(w.play(answer, options.clone().build()).unwrap() <= 6);
+            }
+        }
+
+        #[test]
+        fn lookahead_changes_order() {
+            let words = [
+                "faith", "forth", "tests", "serve", "error", "tried", "speak", "floor", "glass",
+                "trees", "color", "motor",
+            ];
+            let greedy: Options = Options {
+                dictionary: Arc::new(Dictionary::parse(&words.join("\n")).unwrap()),
+                prior: Prior::Uniform,
+                ..Options::default()
+            };
+            let lookahead = Options {
+                lookahead_depth: 2,
+                lookahead_width: words.len(),
+                ..greedy.clone()
+            };
+            let order = |options: &Options| -> Vec<String> {
+                let ranked = options.clone().build().rank_guesses(&[]).unwrap();
+                ranked.into_iter().map(|(word, _)| word).collect()
+            };
+            let (one, two) = (order(&greedy), order(&lookahead));
+            // One guess ahead, `forth` looks best, but looking at the guess after it shows that
+            // `tests` leaves less to do.
+            assert_eq!((one[0].as_str(), two[0].as_str()), ("forth", "tests"));
+            assert_ne!(one, two);
+            assert_eq!(lookahead.build().guess(&[]), "tests");
+        }
+    }
+    mod opener {
+        use crate::{Dictionary, Guesser, Opener, Options, Wordle};
+        use std::sync::Arc;
+
+        fn options(opener: Opener) -> Options {
+            Options {
+                opener,
+                cutoff: false,
+                ..super::small_options(0, 300)
+            }
+        }
+
+        #[test]
+        fn fixed() {
+            let word = Dictionary::<5>::embedded().word(42).to_string();
+            let mut solver = options(Opener::Word(word.clone())).build();
+            assert_eq!(solver.guess(&[]), word);
+        }
+
+        #[test]
+        fn not_in_dictionary() {
+            let mut solver = options(Opener::Word("zzzzz".to_string())).build();
+            let computed = options(Opener::Compute).build().guess(&[]);
+            assert_eq!(solver.guess(&[]), computed);
+        }
+
+        #[test]
+        fn compute() {
+            let options = options(Opener::Compute);
+            let ranked = options.clone().build().rank_guesses(&[]).unwrap();
+            assert_eq!(ranked.len(), options.dictionary.len());
+            assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+            assert_eq!(options.clone().build().guess(&[]), ranked[0].0);
+
+            let w = Wordle::with_dictionary(Arc::clone(&options.dictionary));
+            for idx in [0, 100, 299] {
+                let answer = options.dictionary.word(idx);
+                assert!(w.play(answer, options.clone().build()).is_some());
+            }
+        }
+    }
+    mod cache {
+        use crate::{Guesser, HardMode, Options, Wordle};
+        use std::sync::Arc;
+
+        fn options() -> Options {
+            Options {
+                hard_mode: HardMode::Easy,
+                ..super::small_options(100, 400)
+            }
+        }
+
+        fn scores(options: &Options, answers: impl Iterator<Item = usize>) -> Vec<Option<usize>> {
+            let w = Wordle::with_dictionary(Arc::clone(&options.dictionary));
+            answers
+                .map(|idx| w.play(options.dictionary.word(idx), options.clone().build()))
+                .collect()
+        }
+
+        fn uncached() -> Vec<Option<usize>> {
+            let options = Options {
+                cache: false,
+                ..options()
+            };
+            scores(&options, 0..40)
+        }
+
+        #[test]
+        fn shared_across_threads() {
+            let options = options();
+            let halves: Vec<Vec<Option<usize>>> = std::thread::scope(|s| {
+                let options = &options;
+                let first = s.spawn(move || scores(options, 0..20));
+                let second = s.spawn(move || scores(options, 20..40));
+                vec![first.join().unwrap(), second.join().unwrap()]
+            });
+            assert_eq!(halves.concat(), uncached());
+        }
+
+        #[test]
+        fn precompute() {
+            let options = Options {
+                precompute: true,
+                ..options()
+            };
+            assert_eq!(scores(&options, 0..40), uncached());
+        }
+
+        #[test]
+        fn limited_rows() {
+            for precompute in [false, true] {
+                let options = Options {
+                    cache_rows: Some(25),
+                    precompute,
+                    ..options()
+                };
+                assert_eq!(scores(&options, 0..40), uncached());
+            }
+        }
+
+        #[test]
+        fn freed_with_the_last_solver() {
+            // Words that no other test uses, so that nothing else keeps their cache alive.
+            let options = super::small_options(1000, 100);
+            let checksum = options.dictionary.checksum();
+            let mut solver = options.clone().build();
+            // The solver's own initial words, as long as it holds them.
+            let initial = Arc::downgrade(&options.initial_words());
+            drop(options);
+            solver.guess(&[]);
+            assert!(crate::patterns::is_alive(checksum, None));
+            assert!(initial.upgrade().is_some());
+
+            drop(solver);
+            assert!(!crate::patterns::is_alive(checksum, None));
+            assert!(initial.upgrade().is_none());
+        }
+    }
+    mod matrix {
+        use crate::{Dictionary, Matrix, MatrixError, Options, Wordle};
+        use std::{path::PathBuf, sync::Arc};
+
+        fn dictionary() -> Arc<Dictionary> {
+            super::small_options(0, 300).dictionary
+        }
+
+        /// A file in the temporary directory that is unique to this test run.
+        fn path(name: &str) -> PathBuf {
+            std::env::temp_dir().join(format!("popoki-{}-{}.matrix", name, std::process::id()))
+        }
+
+        #[test]
+        fn same_guesses() {
+            let dictionary = dictionary();
+            let path = path("same-guesses");
+            Matrix::write(&dictionary, &path).unwrap();
+            let matrix = Matrix::open(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(matrix.check(&dictionary).is_ok());
+
+            let options = Options {
+                cache: false,
+                dictionary: Arc::clone(&dictionary),
+                ..Options::default()
+            };
+            let with_matrix = Options {
+                matrix: Some(Arc::new(matrix)),
+                ..options.clone()
+            };
+            let w = Wordle::with_dictionary(Arc::clone(&dictionary));
+            for idx in [0, 100, 299] {
+                let answer = dictionary.word(idx);
+                assert_eq!(
+                    w.play(answer, with_matrix.clone().build()),
+                    w.play(answer, options.clone().build())
+                );
+            }
+        }
+
+        #[test]
+        fn invalid() {
+            let dictionary = dictionary();
+            let path = path("invalid");
+            Matrix::write(&dictionary, &path).unwrap();
+            let matrix = Matrix::<5>::open(&path).unwrap();
+            assert!(matches!(
+                matrix.check(&Dictionary::embedded()),
+                Err(MatrixError::OtherDictionary)
+            ));
+            assert!(matches!(
+                Matrix::<4>::open(&path),
+                Err(MatrixError::Letters(5))
+            ));
+
+            let mut bytes = std::fs::read(&path).unwrap();
+            bytes[8] += 1;
+            std::fs::write(&path, &bytes).unwrap();
+            assert!(matches!(
+                Matrix::<5>::open(&path),
+                Err(MatrixError::Version(2))
+            ));
+            bytes[8] -= 1;
+            std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+            assert!(matches!(
+                Matrix::<5>::open(&path),
+                Err(MatrixError::NotAMatrix)
+            ));
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+    mod book {
+        use crate::{Book, BookError, Dictionary, Guesser, Options, Prior, Rank, Wordle};
+        use std::sync::Arc;
+
+        fn options() -> Options {
+            super::small_options(0, 300)
+        }
+
+        #[test]
+        fn round_trip() {
+            let book = Book::generate(&options());
+            assert_eq!(Book::parse(&book.to_string()).unwrap(), book);
+            assert!(matches!(
+                Book::<5>::parse("opener trace\nGGBBX after"),
+                Err(BookError::Parse { line: 2, .. })
+            ));
+        }
+
+        #[test]
+        fn stale() {
+            let options = options();
+            let book = Book::generate(&options);
+            assert!(book.check(&options).is_ok());
+
+            let other_options = Options {
+                rank_by: Rank::Minimax,
+                ..options.clone()
+            };
+            assert!(matches!(
+                book.check(&other_options),
+                Err(BookError::OtherOptions)
+            ));
+            let other_dictionary = Options {
+                dictionary: Dictionary::embedded(),
+                ..options.clone()
+            };
+            assert!(matches!(
+                book.check(&other_dictionary),
+                Err(BookError::OtherDictionary)
+            ));
+        }
+
+        #[test]
+        fn other_answers() {
+            let options = options();
+            let with_answers = |skip: usize| {
+                let dictionary = &options.dictionary;
+                let answers = (skip..skip + 50).map(|idx| dictionary.word(idx));
+                Options {
+                    prior: Prior::Answers,
+                    dictionary: Arc::new((**dictionary).clone().with_answers(answers).unwrap()),
+                    ..options.clone()
+                }
+            };
+            let book = Book::generate(&with_answers(0));
+            assert!(book.check(&with_answers(0)).is_ok());
+            assert!(matches!(
+                book.check(&with_answers(100)),
+                Err(BookError::OtherDictionary)
+            ));
+        }
+
+        #[test]
+        fn used() {
+            let options = options();
+            let text = Book::generate(&options).to_string();
+            let opener = options.dictionary.word(42).to_string();
+            let text: String = text
+                .lines()
+                .take(2)
+                .map(|line| format!("{}\n", line))
+                .chain([format!("opener {}", opener)])
+                .collect();
+            let with_book = Options {
+                book: Some(Arc::new(Book::parse(&text).unwrap())),
+                ..options
+            };
+            assert_eq!(with_book.build().guess(&[]), opener);
+        }
+
+        #[test]
+        fn same_guesses() {
+            let options = options();
+            let with_book = Options {
+                book: Some(Arc::new(Book::generate(&options))),
+                ..options.clone()
+            };
+            let w = Wordle::with_dictionary(Arc::clone(&options.dictionary));
+            for idx in [0, 100, 299] {
+                let answer = options.dictionary.word(idx);
+                assert_eq!(
+                    w.play(answer, with_book.clone().build()),
+                    w.play(answer, options.clone().build())
+                );
+            }
+        }
+    }
+    mod estimator {
+        use crate::{Estimator, Model, Options, Wordle};
+
+        #[test]
+        fn piecewise_linear() {
+            let e = Estimator::PiecewiseLinear(vec![(0.0, 1.0), (2.0, 2.0), (4.0, 4.0)]);
+            assert_eq!(e.steps_left(-1.0), 1.0);
+            assert_eq!(e.steps_left(1.0), 1.5);
+            assert_eq!(e.steps_left(3.0), 3.0);
+            assert_eq!(e.steps_left(5.0), 4.0);
+        }
+
+        #[test]
+        fn parse() {
+            for e in [
+                Estimator::default(),
+                Estimator::PiecewiseLinear(vec![(0.5, 1.25), (2.0, 2.5)]),
+            ] {
+                assert_eq!(Estimator::parse(&e.to_string()).unwrap(), e);
+            }
+            assert!(Estimator::parse("log 1.0").is_err());
+            assert!(Estimator::parse("piecewise-linear 2 1 1 2").is_err());
+        }
+
+        #[test]
+        fn fit() {
+            let w = Wordle::new();
+            let options: Options = Options::default();
+            let mut samples = Vec::new();
+            for answer in ["cigar", "rebut", "sissy", "humph", "awake", "blush"] {
+                let mut solver = options.clone().build();
+                let guesses = w.play(answer, &mut solver).unwrap();
+                samples.extend(solver.samples(guesses));
+            }
+            assert!(samples
+                .iter()
+                .all(|&(entropy, guesses)| entropy > 0.0 && guesses > 0));
+
+            for model in [Model::Log, Model::PiecewiseLinear] {
+                let fitted = Estimator::fit(model, &samples).unwrap();
+                assert!(fitted.error(&samples) <= options.estimator.error(&samples));
+            }
+            assert!(Estimator::fit(Model::Log, &samples[..1]).is_none());
+        }
+    }
+    mod prior {
+        use crate::{Dictionary, Options, Prior, Wordle};
+        use std::sync::Arc;
+
+        #[test]
+        fn weights() {
+            let d = Dictionary::<4>::parse("abba 6\nboat 3\ncoat 1")
+                .unwrap()
+                .with_answers(["coat"])
+                .unwrap();
+            assert_eq!(Prior::Uniform.weights(&d), [1.0, 1.0, 1.0]);
+            assert_eq!(Prior::Frequency.weights(&d), [0.6, 0.3, 0.1]);
+            assert_eq!(Prior::ByRank.weights(&d), [1.0, 0.5, 1.0 / 3.0]);
+            assert_eq!(
+                Prior::PowerLaw { exponent: 0.0 }.weights(&d),
+                [1.0, 1.0, 1.0]
+            );
+            assert_eq!(Prior::Answers.weights(&d), [0.0, 0.0, 1.0]);
+
+            let sigmoid = Prior::Sigmoid { k: 100.0, x0: 0.2 }.weights(&d);
+            assert!(sigmoid[0] > 0.99 && sigmoid[2] < 0.0001);
+            let sharp = Prior::Sigmoid { k: 1e9, x0: 0.5 }.weights(&d);
+            assert!(sharp.iter().all(|&p| p > 0.0));
+        }
+
+        #[test]
+        fn answers_only() {
+            let w = Wordle::new();
+            let options: Options = Options {
+                prior: Prior::Answers,
+                ..Options::default()
+            };
+            let mut solver = options.clone().build();
+            let answers = w.dictionary().answers().count();
+            assert_eq!(solver.candidates().count(), answers);
+            for answer in ["cigar", "rebut", "sissy", "humph"] {
+                assert!(w.play(answer, &mut solver).unwrap() <= 6);
+            }
+        }
+
+        #[test]
+        fn answers_fall_back_to_uniform() {
+            let d = Arc::new(Dictionary::<4>::parse("abba\nboat\ncoat").unwrap());
+            let options = Options {
+                prior: Prior::Answers,
+                ..Options::with_dictionary(Arc::clone(&d))
+            };
+            assert_eq!(options.build().candidates().count(), 3);
+        }
+
+        #[test]
+        fn answers_of_each_dictionary() {
+            let candidates = |answers: &[&str]| {
+                let d = Dictionary::<4>::parse("abba\nboat\ncoat\ndoor")
+                    .unwrap()
+                    .with_answers(answers)
+                    .unwrap();
+                let options = Options {
+                    prior: Prior::Answers,
+                    ..Options::with_dictionary(Arc::new(d))
+                };
+                let solver = options.build();
+                solver.candidates().map(str::to_string).collect::<Vec<_>>()
+            };
+            assert_eq!(candidates(&["coat"]), ["coat"]);
+            assert_eq!(candidates(&["abba", "door"]), ["abba", "door"]);
+        }
+    }
+    mod endgame {
+        use crate::{endgame::best_guess, HardMode, Options, Wordle};
+
+        /// The candidates that end in `ight` with their initial probabilities.
+        fn ight(options: &Options) -> Vec<(usize, f64)> {
+            options
+                .initial_words()
+                .iter()
+                .filter(|&&(idx, _)| options.dictionary.word(idx).ends_with("ight"))
+                .copied()
+                .collect()
+        }
+
+        #[test]
+        fn two_candidates() {
+            let options: Options = Options::default();
+            let set = &ight(&options)[..2];
+            let (cost, guess) = best_guess(&options, set, None, &mut Default::default()).unwrap();
+            let (p1, p2) = (set[0].1, set[1].1);
+            assert_eq!(guess, set[0].0);
+            assert!((cost - (1.0 + p2 / (p1 + p2))).abs() < 1e-9);
+        }
+
+        #[test]
+        fn any_word_beats_candidates() {
+            let strict: Options = Options::default();
+            let set = &ight(&strict)[..8];
+            let easy: Options = Options {
+                hard_mode: HardMode::Easy,
+                ..Options::default()
+            };
+            let (candidates_only, guess) =
+                best_guess(&strict, set, None, &mut Default::default()).unwrap();
+            assert!(set.iter().any(|&(idx, _)| idx == guess));
+            let (any_word, _) = best_guess(&easy, set, None, &mut Default::default()).unwrap();
+            assert!(any_word < candidates_only);
+
+            // Guessing the candidates one by one can't get through them all in three guesses,
+            // but splitting them up with other words can.
+            assert_eq!(
+                best_guess(&strict, set, Some(3), &mut Default::default()),
+                None
+            );
+            assert!(best_guess(&easy, set, Some(3), &mut Default::default()).is_some());
+        }
+
+        #[test]
+        fn solver() {
+            let w = Wordle::new();
+            let options: Options = Options {
+                endgame: 30,
+                max_guesses: Some(6),
+                ..Options::default()
+            };
+            for answer in ["cigar", "rebut", "sissy"] {
+                assert!(w.play(answer, options.clone().build()).unwrap() <= 6);
+            }
+        }
+    }
+    mod pattern {
+        use crate::{PackedCorrectness, ParsePatternError, Pattern};
+
+        #[test]
+        fn parse() {
+            let expected = Pattern(mask![C M W W C]);
+            assert_eq!("GYBBG".parse(), Ok(expected));
+            assert_eq!("gybbg".parse(), Ok(expected));
+            assert_eq!("CMWWC".parse(), Ok(expected));
+            assert_eq!("🟩🟨⬛⬛🟩".parse(), Ok(expected));
+            assert_eq!("🟩🟨⬜⬜🟩".parse(), Ok(expected));
+            assert_eq!("21002".parse(), Ok(expected));
+        }
+
+        #[test]
+        fn parse_errors() {
+            assert_eq!(
+                "GYBB".parse::<Pattern>(),
+                Err(ParsePatternError::Length {
+                    expected: 5,
+                    found: 4
+                })
+            );
+            assert_eq!(
+                "🟩🟨⬛⬛🟩🟩".parse::<Pattern>(),
+                Err(ParsePatternError::Length {
+                    expected: 5,
+                    found: 6
+                })
+            );
+            assert_eq!(
+                "GYBBX".parse::<Pattern>(),
+                Err(ParsePatternError::Symbol('X'))
+            );
+        }
+
+        #[test]
+        fn display() {
+            let pattern = Pattern(mask![C M W W C]);
+            assert_eq!(pattern.to_string(), "GYBBG");
+            assert_eq!(format!("{:#}", pattern), "🟩🟨⬛⬛🟩");
+        }
+
+        #[test]
+        fn packed_round_trip() {
+            for a in crate::DICTIONARY.iter().take(50) {
+                for b in crate::DICTIONARY.iter().take(50) {
+                    let pattern: Pattern = Pattern(crate::Correctness::compute(a.0, b.0));
+                    let packed = PackedCorrectness::from(pattern);
+                    assert_eq!(Pattern::from(packed), pattern);
+                    assert_eq!(pattern.to_string().parse(), Ok(pattern));
+                }
+            }
+        }
+    }
+    mod packed {
+        use crate::{Correctness, Dictionary, PackedWords, Pattern};
+
+        fn check<const N: usize>(words: &[&str], guesses: &[&str]) {
+            let packed = PackedWords::<N>::new(words.iter().copied());
+            assert_eq!(packed.len(), words.len());
+            let mut patterns = Vec::new();
+            for guess in guesses {
+                packed.patterns(guess, &mut patterns);
+                let expected = words
+                    .iter()
+                    .map(|answer| Pattern(Correctness::compute::<N>(answer, guess)).index());
+                assert!(patterns.iter().copied().eq(expected), "guess '{}'", guess);
+            }
+        }
+
+        #[test]
+        fn same_as_compute() {
+            let dictionary = Dictionary::<5>::embedded();
+            let words: Vec<&str> = dictionary.words().map(|(word, _)| word).collect();
+            let guesses: Vec<&str> = words.iter().step_by(97).copied().collect();
+            check::<5>(&words, &guesses);
+            check::<5>(&words, &["eerie", "llama", "sassy", "eeeee", "abcde"]);
+        }
+
+        #[test]
+        fn other_lengths() {
+            let words = ["abca", "aabb", "bbbb", "abcd", "dcba", "aaaa"];
+            check::<4>(&words, &words);
+            let words = ["abcdefg", "gbcdefa", "aaaaaaa", "abababa", "gfedcba"];
+            check::<7>(&words, &words);
+            check::<5>(&[], &["abcde"]);
+        }
+    }
+    mod length {
+        use crate::{Correctness, Guess, Pattern, WidePackedCorrectness};
+        use std::borrow::Cow;
+
+        #[test]
+        fn four_letters() {
+            assert_eq!(Correctness::compute("abca", "aabb"), mask![C M M W]);
+            assert!(Guess {
+                word: Cow::Borrowed("aabb"),
+                mask: mask![C M M W]
+            }
+            .matches("abca"));
+            assert_eq!(Pattern::<4>::COUNT, 81);
+        }
+
+        #[test]
+        fn seven_letters() {
+            let c = Correctness::compute("abcdefg", "gbcdefa");
+            assert_eq!(c, mask![M C C C C C M]);
+            assert_eq!(Pattern::<7>::COUNT, 2187);
+
+            let packed = WidePackedCorrectness::from(c);
+            assert_eq!(Pattern::from(packed), Pattern(c));
+            assert_eq!("YGGGGGY".parse(), Ok(Pattern(c)));
+        }
+
+        #[test]
+        fn index_round_trip() {
+            for i in 0..Pattern::<6>::COUNT {
+                assert_eq!(Pattern::<6>::from_index(i).index(), i);
+            }
+        }
+    }
+    mod boards {
+        use crate::{Correctness, Error, MultiGuess, MultiGuesser, Options, Wordle};
+        use std::borrow::Cow;
+
+        #[test]
+        fn one_word_per_board() {
+            let w = Wordle::new();
+            fn guesser(history: &[MultiGuess]) -> String {
+                ["right", "wrong", "cigar"][history.len()].to_string()
+            }
+            let guesser = guesser as fn(&[MultiGuess]) -> String;
+            assert_eq!(
+                w.play_boards(&["cigar", "right", "wrong"], guesser),
+                Some(3)
+            );
+            assert_eq!(w.play_boards(&["right", "wrong"], guesser), Some(2));
+        }
+
+        #[test]
+        fn solved_boards_hide_feedback() {
+            let w = Wordle::new();
+            fn guesser(history: &[MultiGuess]) -> String {
+                match history {
+                    [] => "right",
+                    [first] => {
+                        assert_eq!(first.masks[0], Some(mask![C C C C C]));
+                        "cigar"
+                    }
+                    [_, second] => {
+                        assert_eq!(second.masks[0], None);
+                        assert!(second.masks[1].is_some());
+                        "wrong"
+                    }
+                    _ => unreachable!(),
+                }
+                .to_string()
+            }
+            let guesser = guesser as fn(&[MultiGuess]) -> String;
+            assert_eq!(w.play_boards(&["right", "wrong"], guesser), Some(3));
+        }
+
+        #[test]
+        fn quordle() {
+            let w = Wordle::new();
+            let answers = ["cigar", "rebut", "sissy", "humph"];
+            let solver = Options::default().build_multi(answers.len());
+            let guesses = w.play_boards(&answers, solver).unwrap();
+            assert!((4..=9).contains(&guesses), "took {} guesses", guesses);
+        }
+
+        #[test]
+        fn errors() {
+            let w = Wordle::new();
+            fn guesser(_history: &[MultiGuess]) -> String {
+                "zzzzz".to_string()
+            }
+            let guesser = guesser as fn(&[MultiGuess]) -> String;
+            assert_eq!(
+                w.try_play_boards(&["right", "wrong"], guesser),
+                Err(Error::NotInDictionary("zzzzz".to_string()))
+            );
+            assert!(matches!(
+                w.try_play_boards(&["right", "wrng"], guesser),
+                Err(Error::InvalidWord { .. })
+            ));
+
+            let guess = |masks: Vec<Option<[Correctness; 5]>>| MultiGuess {
+                word: Cow::Borrowed("right"),
+                masks,
+            };
+            let mut solver = Options::default().build_multi(2);
+            let before: Vec<usize> = (0..2).map(|b| solver.candidates(b).count()).collect();
+            assert_eq!(
+                solver.try_guess(&[guess(vec![Some(mask![W W W W W])])]),
+                Err(Error::Boards {
+                    guess: 1,
+                    expected: 2,
+                    found: 1
+                })
+            );
+            // No word gets all green but the last letter.
+            assert!(matches!(
+                solver.try_guess(&[guess(vec![Some(mask![W W W W W]), Some(mask![C C C C W])])]),
+                Err(Error::Inconsistent { guess: 1, .. })
+            ));
+            let sissy = MultiGuess {
+                word: Cow::Borrowed("sissy"),
+                masks: vec![Some(mask![W W M W W]), Some(mask![W W W W W])],
+            };
+            assert!(matches!(
+                solver.try_guess(&[sissy]),
+                Err(Error::ImpossiblePattern { letter: 's', .. })
+            ));
+            let after: Vec<usize> = (0..2).map(|b| solver.candidates(b).count()).collect();
+            assert_eq!(before, after);
+
+            let mut solver = Options::default().build_multi(2);
+            assert_eq!(
+                solver.try_guess(&[guess(vec![Some(mask![C C C C C]), None])]),
+                Err(Error::Solved)
+            );
+        }
+    }
+    mod bitset {
+        use crate::{bitset::Bitset, Correctness, Guess, Options, Pattern};
+        use std::borrow::Cow;
+
+        #[test]
+        fn operations() {
+            let mut set = Bitset::with_indices(200, [3, 64, 65, 199]);
+            assert_eq!(set.len(), 4);
+            assert!(set.contains(64) && !set.contains(63));
+            set.intersect(&Bitset::with_indices(200, [0, 3, 65, 150]));
+            assert_eq!(set.iter().collect::<Vec<_>>(), [3, 65]);
+            set.union(&Bitset::with_indices(200, [1, 199]));
+            assert_eq!(set.iter().collect::<Vec<_>>(), [1, 3, 65, 199]);
+            assert_eq!(Bitset::new(0).iter().count(), 0);
+        }
+
+        #[test]
+        fn narrow() {
+            for cache in [true, false] {
+                let options: Options = Options {
+                    cache,
+                    ..Options::default()
+                };
+                let dictionary = &options.dictionary;
+                let all: Vec<(usize, f64)> = (0..dictionary.len()).map(|idx| (idx, 1.0)).collect();
+                for (guess_idx, answer) in [(0, "crane"), (42, "pupil"), (1000, "eerie")] {
+                    let guess = Guess {
+                        word: Cow::Borrowed(dictionary.word(guess_idx)),
+                        mask: Correctness::compute(answer, dictionary.word(guess_idx)),
+                    };
+                    let mut set = Bitset::with_indices(dictionary.len(), 0..dictionary.len());
+                    crate::bitset::narrow(&options, &mut set, &all, guess_idx, Pattern(guess.mask));
+                    let expected = dictionary
+                        .words()
+                        .enumerate()
+                        .filter(|(_, (word, _))| guess.matches(word))
+                        .map(|(idx, _)| idx);
+                    assert!(set.iter().eq(expected));
+                }
+            }
+        }
+
+        #[test]
+        fn kept_within_rows() {
+            let options = Options {
+                cache_rows: Some(1),
+                ..super::small_options(200, 400)
+            };
+            let dictionary = &options.dictionary;
+            let all: Vec<(usize, f64)> = (0..dictionary.len()).map(|idx| (idx, 1.0)).collect();
+            let narrowed: Vec<(usize, usize)> = (0..20)
+                .map(|guess_idx| {
+                    let mask =
+                        Correctness::compute(dictionary.word(399), dictionary.word(guess_idx));
+                    let mut set = Bitset::with_indices(dictionary.len(), 0..dictionary.len());
+                    crate::bitset::narrow(&options, &mut set, &all, guess_idx, Pattern(mask));
+                    (guess_idx, Pattern(mask).index())
+                })
+                .collect();
+            let kept = crate::patterns::with(dictionary, Some(1), |cache| {
+                narrowed
+                    .iter()
+                    .map(|&(guess_idx, pattern_idx)| cache.matching(guess_idx, pattern_idx))
+                    .filter(Option::is_some)
+                    .count()
+            });
+            assert_eq!(kept, 8);
+        }
+    }
+    mod dictionary {
+        use crate::{Dictionary, DictionaryError, Guess, Wordle};
+        use std::sync::Arc;
+
+        #[test]
+        fn parse() {
+            let d = Dictionary::<4>::parse("abba 3\nBOAT 10\n\ncoat\n").unwrap();
+            assert_eq!(d.len(), 3);
+            assert_eq!(
+                d.words().collect::<Vec<_>>(),
+                [("boat", 10), ("abba", 3), ("coat", 1)]
+            );
+            assert_eq!(d.index_of("abba"), Some(1));
+            assert!(!d.contains("abbas"));
+            assert_eq!(d.answers().count(), 0);
+        }
+
+        #[test]
+        fn parse_errors() {
+            assert!(matches!(
+                Dictionary::<4>::parse("abba\nboat ten"),
+                Err(DictionaryError::Parse { line: 2, .. })
+            ));
+            assert!(matches!(
+                Dictionary::<4>::parse("abba\nboats"),
+                Err(DictionaryError::Word {
+                    expected_len: 4,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                Dictionary::<4>::parse("abba\nABBA"),
+                Err(DictionaryError::Duplicate(w)) if w == "abba"
+            ));
+            assert!(matches!(
+                Dictionary::<4>::parse("abba").unwrap().with_answers(["boat"]),
+                Err(DictionaryError::UnknownAnswer(w)) if w == "boat"
+            ));
+            assert!(matches!(
+                Dictionary::<4>::parse("\n"),
+                Err(DictionaryError::Empty)
+            ));
+        }
+
+        #[test]
+        fn checksum() {
+            let a = Dictionary::<4>::parse("abba 3\nboat 10").unwrap();
+            let b = Dictionary::<4>::parse("boat 10\nabba 3").unwrap();
+            let c = Dictionary::<4>::parse("boat 10\nabba 4").unwrap();
+            assert_eq!(a.checksum(), b.checksum());
+            assert_ne!(a.checksum(), c.checksum());
+            let (with, without) = (a.clone().with_answers(["boat"]).unwrap(), a);
+            assert_eq!(with.checksum(), without.checksum());
+            assert_ne!(with.answers_checksum(), without.answers_checksum());
+            assert_eq!(
+                Dictionary::<5>::embedded().checksum(),
+                Dictionary::<5>::embedded().checksum()
+            );
+        }
+
+        #[test]
+        fn embedded_lengths() {
+            assert_eq!(Dictionary::<5>::try_embedded().unwrap().len(), 12947);
+            assert!(matches!(
+                Dictionary::<4>::try_embedded(),
+                Err(DictionaryError::Empty)
+            ));
+        }
+
+        #[test]
+        fn play_custom() {
+            let d = Dictionary::<4>::parse("abba\nboat\ncoat")
+                .unwrap()
+                .with_answers(["coat"])
+                .unwrap();
+            let w = Wordle::with_dictionary(Arc::new(d));
+            fn guesser(_history: &[Guess<'_, 4>]) -> String {
+                "coat".to_string()
+            }
+            assert_eq!(
+                w.play("coat", guesser as fn(&[Guess<'_, 4>]) -> String),
+                Some(1)
+            );
+        }
+    }
     mod compute {
         use crate::Correctness;
 
@@ -11,16 +11,38 @@
     clippy::print_stdout,
     clippy::option_if_let_else,
     clippy::expect_used,
-    clippy::integer_arithmetic,
-    clippy::separated_literal_suffix,
+    clippy::arithmetic_side_effects,
     clippy::cast_possible_truncation,
-    clippy::cast_sign_loss
+    clippy::cast_sign_loss,
+    clippy::allow_attributes_without_reason,
+    clippy::arbitrary_source_item_ordering,
+    clippy::single_call_fn,
+    clippy::min_ident_chars,
+    clippy::pattern_type_mismatch,
+    clippy::std_instead_of_alloc,
+    clippy::question_mark_used,
+    clippy::struct_excessive_bools
 )]
 
-use clap::{Parser, ValueEnum};
-use popoki::{Guesser, Solver};
-
-const GAMES: &str = include_str!("../answers.txt");
+use clap::{Parser, Subcommand, ValueEnum};
+use popoki::{
+    Book, Correctness, Dictionary, DictionaryError, Estimator, Guess, Guesser, Matrix,
+    MultiGuesser, Opener, Options, Pattern, Solver,
+};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs, io, iter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -35,22 +57,328 @@ struct Args {
     #[clap(long)]
     no_cache: bool,
 
-    /// By default, only the most likely 1/3 of candidates are considered at each step. This flag
-    /// disables that pruning behavior.
+    /// Only cache the patterns of this many guesses, picked from those used more than once, to
+    /// bound the memory the cache takes. Each guess takes a byte for every word of the dictionary.
+    #[clap(long)]
+    cache_rows: Option<usize>,
+
+    /// Fill the pattern cache on every core before the first guess, rather than as it is used.
+    #[clap(long)]
+    precompute: bool,
+
+    /// By default, only the most likely `--cutoff-fraction` of candidates are considered at each
+    /// step. This flag disables that pruning behavior.
     #[clap(long)]
     no_cutoff: bool,
 
+    /// The fraction of candidates considered at each step, unless `--no-cutoff` is passed.
+    #[clap(long, default_value_t = 1.0 / 3.0)]
+    cutoff_fraction: f64,
+
+    /// The fewest candidates considered at each step, unless `--no-cutoff` is passed.
+    #[clap(long, default_value_t = 20)]
+    cutoff_floor: usize,
+
+    /// Stop considering guesses at each step once none of the rest can be better than the best one
+    /// so far. This never changes which guess is made, so it is a faster `--no-cutoff` when
+    /// combined with it.
+    #[clap(long)]
+    adaptive_cutoff: bool,
+
     /// By default, all games are played in "hard mode" where known-incorrect guesses are
     /// disallowed. This flag allows arbitrary guesses at every step, which allows for more optimal
-    /// guessing, but also increases the search space.
-    #[clap(long)]
+    /// guessing, but also increases the search space. It is short for `--hard-mode easy`.
+    #[clap(long, conflicts_with = "hard-mode")]
     easy: bool,
 
+    /// Set which words the solver may guess.
+    ///
+    /// By default, only words that may still be the answer are guessed.
+    #[clap(long, value_enum, default_value_t = HardMode::Strict)]
+    hard_mode: HardMode,
+
+    /// Set how likely each word is taken to be the answer before any guess is made.
+    ///
+    /// By default, a sigmoid of each word's frequency treats common words as about equally likely
+    /// and rare words as all but impossible.
+    #[clap(long, value_enum, default_value_t = Prior::Sigmoid, global = true)]
+    prior: Prior,
+
+    /// How sharply the sigmoid prior cuts off rare words.
+    #[clap(long, default_value_t = 30_000_000.0, global = true)]
+    sigmoid_k: f64,
+
+    /// The frequency at which the sigmoid prior makes words half as likely as common words.
+    #[clap(long, default_value_t = 0.000_004_97, global = true)]
+    sigmoid_x0: f64,
+
+    /// The exponent that the power law prior raises frequencies to.
+    #[clap(long, default_value_t = 0.5, global = true)]
+    power_law_exponent: f64,
+
+    /// Once this many candidates or fewer remain, search exhaustively for the guess that minimises
+    /// the expected number of guesses left.
+    ///
+    /// By default, the solver relies on its estimates until the end.
+    #[clap(long, default_value_t = 0)]
+    endgame: usize,
+
+    /// Make the endgame search avoid strategies that may take more than this many guesses, such
+    /// as 6 for Wordle.
+    #[clap(long)]
+    max_guesses: Option<usize>,
+
+    /// How many guesses to look ahead when picking a guess.
+    ///
+    /// Beyond 1, the best guesses by `--rank-by` are compared by their expected score that many
+    /// guesses ahead.
+    #[clap(long, default_value_t = 1)]
+    lookahead_depth: usize,
+
+    /// How many of the best guesses to look further ahead from at each step of the lookahead.
+    #[clap(long, default_value_t = 10)]
+    lookahead_width: usize,
+
+    /// The first guess, or `compute` to search for the best one under `--rank-by`, which takes a
+    /// while. The best one is computed once and used for every game.
+    ///
+    /// A word that is not in the dictionary is ignored, and the opener is computed.
+    #[clap(long, default_value = "trace")]
+    opener: String,
+
+    /// Make each guess with the best guess found within this many milliseconds, evaluating the most
+    /// likely guesses first.
+    ///
+    /// By default, every guess is evaluated as set by the cutoff.
+    #[clap(long)]
+    time_budget: Option<u64>,
+
+    /// Make the game enforce the rules of hard mode, and report every guess that breaks them.
+    #[clap(long)]
+    enforce_hard_mode: bool,
+
     /// The number of games to run.
     ///
     /// If not passed, all Wordle games are run.
     #[clap(short, long, default_value_t = 5)]
     games: usize,
+
+    /// The number of boards played at once, such as 4 for Quordle or 8 for Octordle.
+    ///
+    /// Each game takes its answers from the next `boards` answers in order.
+    #[clap(short, long, default_value_t = 1)]
+    boards: usize,
+
+    /// The number of threads to play games on. Each thread builds its own solvers, and the results
+    /// are reported in the same order and with the same totals as with a single thread.
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// A file of the words that may be guessed, one per line, each optionally followed by how
+    /// often it occurs. The length of its words sets the length of the game.
+    ///
+    /// By default, the embedded dictionary of five letter words is used.
+    #[clap(long, global = true)]
+    dictionary: Option<PathBuf>,
+
+    /// A file of whitespace-separated answers to play, all of which must be in the dictionary.
+    ///
+    /// By default, the embedded answers are played. A dictionary given with `--dictionary` has no
+    /// answers of its own, so games can only be played with it when this is given too.
+    #[clap(long, global = true)]
+    answers: Option<PathBuf>,
+
+    /// A profile made by `calibrate` to estimate the number of guesses left with.
+    ///
+    /// By default, an estimate fitted to the embedded dictionary is used.
+    #[clap(long, global = true)]
+    estimator: Option<PathBuf>,
+
+    /// An opening book made by `make-book` to take the first two guesses from. It must have been
+    /// made with the same dictionary and options.
+    #[clap(long, global = true)]
+    book: Option<PathBuf>,
+
+    /// A pattern matrix written by `write-matrix` to map into memory, rather than computing the
+    /// patterns as they are needed. It must have been written for the same dictionary.
+    #[clap(long, global = true)]
+    matrix: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Suggest guesses for a game of Wordle that is being played elsewhere.
+    ///
+    /// After each suggestion, enter the colours the game showed, optionally preceded by the word
+    /// that was actually played, for example `trace GYBBG` or `trace 🟩🟨⬛⬛🟩`. Enter `undo`
+    /// to take back the last guess, `list` to show the remaining candidates and `quit` to stop.
+    Assist,
+
+    /// Rank every word of the dictionary as a first guess, and print the best ones with their
+    /// scores under `--rank-by`. Higher scores are better.
+    BestOpeners {
+        /// How many words to print.
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Print the probability of each word under `--prior`, next to its frequency, least likely
+    /// first.
+    Priors,
+
+    /// Work out the opener and the second guess after each of its patterns with the current
+    /// dictionary and options, and save them as an opening book for `--book`.
+    MakeBook {
+        /// The file to save the book to.
+        path: PathBuf,
+    },
+
+    /// Compute the pattern of every guess and answer of the dictionary on every core, and write
+    /// them to a file for `--matrix`, so that later runs can start guessing right away.
+    WriteMatrix {
+        /// The file to write the matrix to.
+        path: PathBuf,
+    },
+
+    /// Search for the sigmoid prior, cutoff fraction and estimate of the guesses left that solve
+    /// the last `--games` answers in the fewest guesses, and print the best configurations.
+    ///
+    /// Configurations are ranked by the share of games that took more than 6 guesses, and then by
+    /// the average number of guesses. The last answers are used so that the first ones, which
+    /// are played by default, stay held out.
+    Tune(Tune),
+
+    /// Play `--games` games, fit the estimate of the number of guesses left to how many the
+    /// solver needed from each point, and save the fit as a profile for `--estimator`.
+    ///
+    /// The games are played with the current estimate, so running this again with the new
+    /// profile refines it further.
+    Calibrate {
+        /// The file to save the profile to.
+        path: PathBuf,
+
+        /// The shape of the curve to fit.
+        #[clap(long, value_enum, default_value_t = Model::Log)]
+        model: Model,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct Tune {
+    /// How to pick the configurations to try.
+    #[clap(long, value_enum, default_value_t = Search::Grid)]
+    search: Search,
+
+    /// The number of values of each parameter that a grid search tries.
+    #[clap(long, default_value_t = 3)]
+    steps: usize,
+
+    /// The number of configurations that a random search tries.
+    #[clap(long, default_value_t = 50)]
+    configs: usize,
+
+    /// The seed of a random search.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// The range of `--sigmoid-k` to search, which is searched on a log scale.
+    #[clap(long, default_value = "10000000,100000000")]
+    k: Range,
+
+    /// The range of `--sigmoid-x0` to search, which is searched on a log scale.
+    #[clap(long, default_value = "0.0000025,0.00001")]
+    x0: Range,
+
+    /// The range of the fraction of candidates considered at each step to search.
+    #[clap(long, default_value = "0.2,0.5")]
+    cutoff_fraction: Range,
+
+    /// The range of the scale of the estimate `ln(entropy * scale + offset)` to search.
+    #[clap(long, default_value = "2,6")]
+    scale: Range,
+
+    /// The range of the offset of the estimate `ln(entropy * scale + offset)` to search.
+    #[clap(long, default_value = "2.5,5")]
+    offset: Range,
+
+    /// How many of the best configurations to print.
+    #[clap(long, default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Search {
+    /// Every combination of `--steps` evenly spaced values of each parameter.
+    Grid,
+
+    /// `--configs` combinations of values drawn at random.
+    Random,
+}
+
+/// The lowest and highest value of a parameter to search, written as `min,max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Range {
+    min: f64,
+    max: f64,
+}
+
+impl FromStr for Range {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bounds = s
+            .split_once(',')
+            .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)));
+        match bounds {
+            Some((min, max)) if min <= max => Ok(Self { min, max }),
+            _ => Err(format!("'{s}' should be `min,max` with min <= max")),
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Prior {
+    /// A sigmoid of each word's frequency, see `--sigmoid-k` and `--sigmoid-x0`.
+    Sigmoid,
+
+    /// Every word is equally likely.
+    Uniform,
+
+    /// Each word is as likely as it is frequent.
+    Frequency,
+
+    /// 1/n for the nth most frequent word.
+    Rank,
+
+    /// Each word's frequency raised to `--power-law-exponent`.
+    PowerLaw,
+
+    /// Only the answers, which are equally likely.
+    Answers,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Model {
+    /// ln(entropy * scale + offset).
+    Log,
+
+    /// Straight lines between the mean of each bit of entropy.
+    PiecewiseLinear,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum HardMode {
+    /// Any word in the dictionary.
+    Easy,
+
+    /// Any word that uses every hint revealed so far, as in the game's hard mode.
+    Hard,
+
+    /// Only words that may still be the answer.
+    Strict,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -58,71 +386,626 @@ enum Rank {
     /// Just pick the first candidate.
     First,
 
-    /// E[score] = p(word) * (score + 1) + (1 - p(word)) * (score + E[guesses](entropy - E[information]))
+    /// E[score] = p(word) * (score + 1) + (1 - p(word)) * (score + E[guesses](entropy - E[information])).
     ExpectedScore,
 
-    /// p(word) * E[information]
+    /// p(word) * E[information].
     WeightedInformation,
 
-    /// p(word) + E[information]
+    /// p(word) + E[information].
     InfoPlusProbability,
 
-    /// E[information]
+    /// E[information].
     ExpectedInformation,
+
+    /// The smallest probability of the most likely pattern, which limits the worst case,
+    /// preferring candidates on ties.
+    Minimax,
+
+    /// The number of distinct patterns, preferring candidates on ties.
+    MostParts,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
-    let mut solver = Solver::builder();
+    let text = match args.dictionary.as_ref().map(fs::read_to_string) {
+        Some(Ok(text)) => Some(text),
+        Some(Err(e)) => {
+            eprintln!("{}", DictionaryError::from(e));
+            return ExitCode::FAILURE;
+        }
+        None => None,
+    };
+
+    // The game is played with words of the same length as those in the dictionary.
+    let len = text
+        .as_deref()
+        .and_then(|words| words.split_whitespace().next())
+        .map_or(5, str::len);
+    let result = match len {
+        4 => run::<4>(&args, text.as_deref()),
+        5 => run::<5>(&args, text.as_deref()),
+        6 => run::<6>(&args, text.as_deref()),
+        7 => run::<7>(&args, text.as_deref()),
+        8 => run::<8>(&args, text.as_deref()),
+        _ => {
+            eprintln!("{len} letter words are not supported");
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Load the dictionary from `text` and its answers from `answers`, falling back to the embedded
+/// lists for either of them.
+fn load_dictionary<const N: usize>(
+    text: Option<&str>,
+    answers: Option<&Path>,
+) -> Result<Arc<Dictionary<N>>, DictionaryError> {
+    let dictionary = match text {
+        Some(words) => Dictionary::parse(words)?,
+        None if answers.is_none() => return Dictionary::try_embedded(),
+        None => Dictionary::clone(&*Dictionary::try_embedded()?),
+    };
+    Ok(Arc::new(match answers {
+        Some(path) => dictionary.load_answers(path)?,
+        None => dictionary,
+    }))
+}
+
+/// The options that `args` set, with the dictionary in `text`.
+fn options<const N: usize>(args: &Args, text: Option<&str>) -> Result<Options<N>, Box<dyn Error>> {
+    let mut solver = Options::with_dictionary(load_dictionary(text, args.answers.as_deref())?);
 
     if args.no_cache {
         solver.cache = false;
     }
+    solver.cache_rows = args.cache_rows;
+    solver.precompute = args.precompute;
     if args.no_cutoff {
         solver.cutoff = false;
     }
-    if args.easy {
-        solver.hard_mode = false;
-    }
+    solver.cutoff_fraction = args.cutoff_fraction;
+    solver.cutoff_floor = args.cutoff_floor;
+    solver.adaptive_cutoff = args.adaptive_cutoff;
+    solver.time_budget = args.time_budget.map(Duration::from_millis);
+    solver.hard_mode = match (args.easy, args.hard_mode) {
+        (true, _) | (false, HardMode::Easy) => popoki::HardMode::Easy,
+        (false, HardMode::Hard) => popoki::HardMode::Hard,
+        (false, HardMode::Strict) => popoki::HardMode::Strict,
+    };
+    solver.prior = match args.prior {
+        Prior::Sigmoid => popoki::Prior::Sigmoid {
+            k: args.sigmoid_k,
+            x0: args.sigmoid_x0,
+        },
+        Prior::Uniform => popoki::Prior::Uniform,
+        Prior::Frequency => popoki::Prior::Frequency,
+        Prior::Rank => popoki::Prior::ByRank,
+        Prior::PowerLaw => popoki::Prior::PowerLaw {
+            exponent: args.power_law_exponent,
+        },
+        Prior::Answers => popoki::Prior::Answers,
+    };
+    solver.endgame = args.endgame;
+    solver.max_guesses = args.max_guesses;
+    solver.lookahead_depth = args.lookahead_depth;
+    solver.lookahead_width = args.lookahead_width;
     solver.rank_by = match args.rank_by {
         Rank::First => popoki::Rank::First,
         Rank::ExpectedScore => popoki::Rank::ExpectedScore,
         Rank::WeightedInformation => popoki::Rank::WeightedInformation,
         Rank::InfoPlusProbability => popoki::Rank::InfoPlusProbability,
         Rank::ExpectedInformation => popoki::Rank::ExpectedInformation,
+        Rank::Minimax => popoki::Rank::Minimax,
+        Rank::MostParts => popoki::Rank::MostParts,
     };
+    solver.opener = match args.opener.as_str() {
+        "compute" => Opener::Compute,
+        word => Opener::Word(word.to_owned()),
+    };
+
+    if let Some(path) = &args.estimator {
+        solver.estimator = Estimator::load(path)?;
+    }
 
-    play(move || solver.build(), args.games);
+    // A book that is about to be made again may well be stale, so it is not loaded.
+    if let Some(path) = &args.book {
+        if !matches!(args.command, Some(Command::MakeBook { .. })) {
+            let book = Book::load(path)?;
+            book.check(&solver)?;
+            solver.book = Some(Arc::new(book));
+        }
+    }
+    // The matrix that is being written may be the one that would be mapped.
+    if let Some(path) = &args.matrix {
+        if !matches!(args.command, Some(Command::WriteMatrix { .. })) {
+            let matrix = Matrix::open(path)?;
+            matrix.check(&solver.dictionary)?;
+            solver.matrix = Some(Arc::new(matrix));
+        }
+    }
+    Ok(solver)
+}
+
+fn run<const N: usize>(args: &Args, text: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut solver = options::<N>(args, text)?;
+    let plays = matches!(
+        args.command,
+        None | Some(Command::Tune(_) | Command::Calibrate { .. })
+    );
+    if plays && solver.dictionary.answers().next().is_none() {
+        return Err("the dictionary has no answers to play; give them with --answers".into());
+    }
+    match &args.command {
+        Some(Command::Assist) => assist(&solver),
+        Some(Command::MakeBook { path }) => Book::generate(&solver).save(path)?,
+        Some(Command::WriteMatrix { path }) => Matrix::write(&solver.dictionary, path)?,
+        Some(Command::BestOpeners { top }) => best_openers(&solver, *top),
+        Some(Command::Priors) => print_priors(&solver),
+        Some(Command::Tune(search)) => tune(&solver, search, args.games),
+        Some(Command::Calibrate { path, model }) => {
+            let shape = match model {
+                Model::Log => popoki::Model::Log,
+                Model::PiecewiseLinear => popoki::Model::PiecewiseLinear,
+            };
+            calibrate(&solver, shape, args.games)?.save(path)?;
+        }
+        None if args.boards > 1 => {
+            // Rather than have every game search for the same opener, search for it once.
+            if solver.opener == Opener::Compute {
+                let opener = solver.clone().build_multi(args.boards).guess(&[]);
+                solver.opener = Opener::Word(opener);
+            }
+            let wordle = popoki::Wordle::with_dictionary(Arc::clone(&solver.dictionary));
+            play_boards(
+                &wordle,
+                args.boards,
+                || solver.clone().build_multi(args.boards),
+                args.games,
+                args.jobs,
+            );
+        }
+        None => {
+            // A book already has the opener, and only holds for the options it was made with.
+            if solver.opener == Opener::Compute && solver.book.is_none() {
+                let opener = solver.clone().build().guess(&[]);
+                solver.opener = Opener::Word(opener);
+            }
+            let wordle = popoki::Wordle::with_dictionary(Arc::clone(&solver.dictionary))
+                .with_hard_mode(args.enforce_hard_mode);
+            let evaluated = play(
+                &wordle,
+                || solver.clone().build(),
+                args.games,
+                args.jobs,
+                |played: &Solver<N>| played.evaluated().to_vec(),
+            )
+            .concat();
+            if let Some(fewest) = evaluated.iter().min() {
+                eprintln!(
+                    "evaluated {:.1} guesses per ranked guess on average, and at least {}",
+                    evaluated.iter().sum::<usize>() as f64 / evaluated.len() as f64,
+                    fewest
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn best_openers<const N: usize>(options: &Options<N>, top: usize) {
+    let ranked = options
+        .clone()
+        .build()
+        .rank_guesses(&[])
+        .expect("there is no feedback to contradict");
+    for (rank, (word, score)) in ranked.iter().take(top).enumerate() {
+        println!("{:>3}. {word} {score:.4}", rank + 1);
+    }
+}
+
+fn print_priors<const N: usize>(options: &Options<N>) {
+    let dictionary = &options.dictionary;
+    let weights = options.prior.weights(dictionary);
+    let total_weight: f64 = weights.iter().sum();
+    let total_count: usize = dictionary.words().map(|(_, count)| count).sum();
+    for ((word, count), weight) in dictionary.words().rev().zip(weights.iter().rev()) {
+        println!(
+            "{} {:.6}% -> {:.6}% ({})",
+            word,
+            100.0_f64 * count as f64 / total_count as f64,
+            100.0_f64 * weight / total_weight,
+            count
+        );
+    }
+}
+
+/// A configuration that `tune` tries: the sigmoid's K and X0, the cutoff fraction, and the scale
+/// and offset of the estimate of the guesses left.
+type Config = [f64; 5];
+
+/// Whether each parameter of a [`Config`] is searched on a log scale.
+const LOG_SCALE: [bool; 5] = [true, true, false, false, false];
+
+/// The value at `t` between 0 and 1 of the way through the range of parameter `param`.
+fn interpolate(range: Range, param: usize, t: f64) -> f64 {
+    if LOG_SCALE.get(param) == Some(&true) {
+        t.mul_add(range.max.ln() - range.min.ln(), range.min.ln())
+            .exp()
+    } else {
+        t.mul_add(range.max - range.min, range.min)
+    }
+}
+
+/// Every combination of `steps` evenly spaced values in each of `ranges`.
+fn grid(ranges: &[Range; 5], steps: usize) -> Vec<Config> {
+    let t = |step: usize| {
+        if steps <= 1 {
+            0.5_f64
+        } else {
+            step as f64 / (steps - 1) as f64
+        }
+    };
+    (0..steps.max(1).pow(5))
+        .map(|i| {
+            let mut config = [0.0_f64; 5];
+            let mut rest = i;
+            for (param, (value, &range)) in config.iter_mut().zip(ranges).enumerate() {
+                *value = interpolate(range, param, t(rest.rem_euclid(steps.max(1))));
+                rest /= steps.max(1);
+            }
+            config
+        })
+        .collect()
+}
+
+/// `configs` combinations of values drawn at random from `ranges`.
+fn random(ranges: &[Range; 5], configs: usize, seed: u64) -> Vec<Config> {
+    // SplitMix64, which is plenty for spreading out a few configurations.
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30_u32)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27_u32)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31_u32)) as f64 / u64::MAX as f64
+    };
+    iter::repeat_with(|| {
+        let mut config = [0.0_f64; 5];
+        for (param, (value, &range)) in config.iter_mut().zip(ranges).enumerate() {
+            *value = interpolate(range, param, next());
+        }
+        config
+    })
+    .take(configs)
+    .collect()
+}
+
+/// Play the last `games` answers with `options`, returning the average number of guesses of the
+/// games that were solved, or `None` if none were, and the share of games that took more than 6
+/// guesses.
+fn evaluate<const N: usize>(options: &Options<N>, games: usize) -> (Option<f64>, f64) {
+    let wordle = popoki::Wordle::with_dictionary(Arc::clone(&options.dictionary));
+    let answers = options.dictionary.answers().count();
+    let (mut played, mut solved, mut guesses, mut failed) = (0_usize, 0_usize, 0_usize, 0_usize);
+    for answer in options
+        .dictionary
+        .answers()
+        .skip(answers.saturating_sub(games))
+    {
+        played += 1;
+        match wordle.try_play(answer, options.clone().build()) {
+            Ok(Some(score)) => {
+                solved += 1;
+                guesses += score;
+                if score > 6 {
+                    failed += 1;
+                }
+            }
+            Ok(None) => failed += 1,
+            Err(e) => {
+                eprintln!("Failed to play '{answer}': {e}");
+                failed += 1;
+            }
+        }
+    }
+    let average = (solved > 0).then(|| guesses as f64 / solved as f64);
+    (average, failed as f64 / played.max(1) as f64)
+}
+
+/// Try each configuration that `search` picks on top of `options`, and print the best ones next to
+/// `options` itself.
+fn tune<const N: usize>(options: &Options<N>, search: &Tune, games: usize) {
+    let ranges = [
+        search.k,
+        search.x0,
+        search.cutoff_fraction,
+        search.scale,
+        search.offset,
+    ];
+    let configs = match search.search {
+        Search::Grid => grid(&ranges, search.steps),
+        Search::Random => random(&ranges, search.configs, search.seed),
+    };
+    let apply = |&[k, x0, cutoff_fraction, scale, offset]: &Config| {
+        let mut tuned = options.clone();
+        tuned.prior = popoki::Prior::Sigmoid { k, x0 };
+        tuned.cutoff_fraction = cutoff_fraction;
+        tuned.estimator = Estimator::Log { scale, offset };
+        tuned
+    };
+    let mut results: Vec<(Config, (Option<f64>, f64))> = configs
+        .iter()
+        .map(|config| (*config, evaluate(&apply(config), games)))
+        .collect();
+    results.sort_by(|(_, (a_average, a_failed)), (_, (b_average, b_failed))| {
+        // Configurations that solved nothing come last.
+        let unsolved_last = |average: &Option<f64>| average.unwrap_or(f64::INFINITY);
+        a_failed
+            .total_cmp(b_failed)
+            .then(unsolved_last(a_average).total_cmp(&unsolved_last(b_average)))
+    });
+    let show =
+        |average: Option<f64>| average.map_or_else(|| "unsolved".to_owned(), |a| format!("{a:.4}"));
+
+    println!(
+        "{:>7} {:>12} {:>12} {:>7} {:>7} {:>7} {:>8} {:>9}",
+        "rank", "k", "x0", "cutoff", "scale", "offset", "average", "failures"
+    );
+    let (average, failed) = evaluate(options, games);
+    println!(
+        "{:>7} {:>63} {:>8} {:>8.2}%",
+        "current",
+        "",
+        show(average),
+        100.0_f64 * failed
+    );
+    for (rank, ([k, x0, cutoff_fraction, scale, offset], (score, failures))) in
+        results.iter().take(search.top).enumerate()
+    {
+        println!(
+            "{:>7} {:>12.0} {:>12.8} {:>7.3} {:>7.3} {:>7.3} {:>8} {:>8.2}%",
+            rank + 1,
+            k,
+            x0,
+            cutoff_fraction,
+            scale,
+            offset,
+            show(*score),
+            100.0_f64 * failures
+        );
+    }
+}
+
+/// Play `games` games and fit an estimator of shape `model` to them.
+fn calibrate<const N: usize>(
+    options: &Options<N>,
+    model: popoki::Model,
+    games: usize,
+) -> Result<Estimator, String> {
+    let wordle = popoki::Wordle::with_dictionary(Arc::clone(&options.dictionary));
+    let mut samples = Vec::new();
+    for answer in options.dictionary.answers().take(games) {
+        let mut solver = options.clone().build();
+        match wordle.try_play(answer, &mut solver) {
+            Ok(Some(guesses)) => samples.extend(solver.samples(guesses)),
+            Ok(None) => eprintln!("Failed to guess '{answer}'"),
+            Err(e) => eprintln!("Failed to play '{answer}': {e}"),
+        }
+    }
+
+    let fitted = Estimator::fit(model, &samples)
+        .ok_or_else(|| format!("{} samples are too few to fit the estimator", samples.len()))?;
+    println!(
+        "fitted to {} samples, with an error of {:.4} guesses (was {:.4})",
+        samples.len(),
+        fitted.error(&samples),
+        options.estimator.error(&samples)
+    );
+    print!("{fitted}");
+    Ok(fitted)
+}
+
+fn assist<const N: usize>(options: &Options<N>) {
+    let mut line = String::new();
+    let mut history: Vec<Guess<'static, N>> = Vec::new();
+    // The solver and its suggestion after each step, kept around so that guesses can be undone.
+    let mut first = options.clone().build();
+    let opener = first.guess(&history);
+    let mut steps: Vec<(Solver<N>, String)> = vec![(first, opener)];
+
+    println!("enter `[word] <pattern>`, `undo`, `list` or `quit`");
+    loop {
+        let (solver, suggestion) = steps.last().expect("there is always a suggestion");
+        println!(
+            "guess {}: {} ({} candidates remaining)",
+            history.len() + 1,
+            suggestion,
+            solver.candidates().count()
+        );
+
+        line.clear();
+        if !matches!(io::stdin().read_line(&mut line), Ok(n) if n > 0) {
+            break;
+        }
+        let mut parts = line.split_whitespace();
+        let (word, pattern) = match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => continue,
+            (Some("quit"), None, _) => break,
+            (Some("undo"), None, _) => {
+                if history.pop().is_some() {
+                    steps.pop();
+                } else {
+                    println!("nothing to undo");
+                }
+                continue;
+            }
+            (Some("list"), None, _) => {
+                for word in solver.candidates() {
+                    println!("{word}");
+                }
+                continue;
+            }
+            (Some(pattern), None, _) => (suggestion.clone(), pattern),
+            (Some(word), Some(pattern), None) => (word.to_ascii_lowercase(), pattern),
+            _ => {
+                println!("expected `[word] <pattern>`, `undo`, `list` or `quit`");
+                continue;
+            }
+        };
+
+        let mask = match pattern.parse::<Pattern<N>>() {
+            Ok(mask) => mask.into(),
+            Err(e) => {
+                println!("'{pattern}' is not a pattern: {e}");
+                continue;
+            }
+        };
+        if mask == [Correctness::Correct; N] {
+            println!("solved in {}", history.len() + 1);
+            break;
+        }
+
+        history.push(Guess {
+            word: Cow::Owned(word),
+            mask,
+        });
+        let mut next = solver.clone();
+        match next.try_guess(&history) {
+            Ok(next_suggestion) => steps.push((next, next_suggestion)),
+            Err(e) => {
+                println!("{e}");
+                history.pop();
+            }
+        }
+    }
 }
 
-fn play<G>(mut mk: impl FnMut() -> G, max: usize)
+/// Play the first `max` answers on `jobs` threads, with a new guesser from `mk` for each, and print
+/// the histogram of their scores. Returns what `inspect` makes of each guesser once its game is
+/// over, in the order of the answers.
+fn play<G, T, const N: usize>(
+    w: &popoki::Wordle<N>,
+    mk: impl Fn() -> G + Sync,
+    max: usize,
+    jobs: usize,
+    inspect: impl Fn(&G) -> T + Sync,
+) -> Vec<T>
 where
-    G: Guesser,
+    G: Guesser<N>,
+    T: Send,
 {
-    let w = popoki::Wordle::new();
-    let mut score = 0;
-    let mut games = 0_i32;
+    let answers: Vec<&str> = w.dictionary().answers().take(max).collect();
+    let games = parallel(&answers, jobs, |&answer| {
+        let mut guesser = (mk)();
+        let result = w.try_play(answer, &mut guesser);
+        (result, inspect(&guesser))
+    });
+
     let mut histogram = Vec::new();
+    let mut inspected = Vec::with_capacity(games.len());
+    for (answer, (result, seen)) in answers.iter().zip(games) {
+        match result {
+            Ok(Some(s)) => record(&mut histogram, s),
+            Ok(None) => eprintln!("Failed to guess '{answer}'"),
+            Err(e) => eprintln!("Failed to play '{answer}': {e}"),
+        }
+        inspected.push(seen);
+    }
+    print_histogram(&histogram);
+    inspected
+}
 
-    for answer in GAMES.split_whitespace().take(max) {
-        let guesser = (mk)();
-        if let Some(s) = w.play(answer, guesser) {
-            games += 1_i32;
-            score += s;
-            if s >= histogram.len() {
-                histogram.extend(std::iter::repeat(0).take(s - histogram.len() + 1));
-            }
-            *histogram
-                .get_mut(s)
-                .expect("Failed while indexing histogram") += 1;
-        } else {
-            eprintln!("Failed to guess '{answer}'");
+fn play_boards<G, const N: usize>(
+    w: &popoki::Wordle<N>,
+    boards: usize,
+    mk: impl Fn() -> G + Sync,
+    max: usize,
+    jobs: usize,
+) where
+    G: MultiGuesser<N>,
+{
+    let all_answers: Vec<&str> = w.dictionary().answers().collect();
+    let games: Vec<&[&str]> = all_answers.chunks_exact(boards).take(max).collect();
+    let scores = parallel(&games, jobs, |answers| w.try_play_boards(answers, (mk)()));
+
+    let mut histogram = Vec::new();
+    for (answers, score) in games.iter().zip(scores) {
+        let joined = answers.join(" ");
+        match score {
+            Ok(Some(s)) => record(&mut histogram, s),
+            Ok(None) => eprintln!("Failed to guess '{joined}'"),
+            Err(e) => eprintln!("Failed to play '{joined}': {e}"),
         }
     }
+    print_histogram(&histogram);
+}
 
-    let sum: usize = histogram.iter().sum();
-    for (score_h, count) in histogram.into_iter().enumerate().skip(1) {
-        let frac = count as f64 / sum as f64;
+/// Call `f` with each of `inputs` on `jobs` threads, which take the next input as soon as they are
+/// done with the last one, and return the results in the order of `inputs`.
+fn parallel<I, T>(inputs: &[I], jobs: usize, f: impl Fn(&I) -> T + Sync) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+{
+    if jobs <= 1 {
+        return inputs.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, T)> = thread::scope(|s| {
+        let workers: Vec<_> = iter::repeat_with(|| {
+            s.spawn(|| {
+                let mut done = Vec::new();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(input) = inputs.get(idx) else {
+                        break done;
+                    };
+                    done.push((idx, f(input)));
+                }
+            })
+        })
+        .take(jobs)
+        .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("a game panicked"))
+            .collect()
+    });
+    results.sort_by_key(|&(idx, _)| idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Count a game that took `score` guesses.
+fn record(histogram: &mut Vec<usize>, score: usize) {
+    if score >= histogram.len() {
+        histogram.resize(score + 1, 0);
+    }
+    *histogram
+        .get_mut(score)
+        .expect("Failed while indexing histogram") += 1;
+}
+
+fn print_histogram(histogram: &[usize]) {
+    let games: usize = histogram.iter().sum();
+    if games == 0 {
+        eprintln!("no games were solved");
+        return;
+    }
+    let score: usize = histogram
+        .iter()
+        .enumerate()
+        .map(|(score_h, count)| score_h * count)
+        .sum();
+    for (score_h, &count) in histogram.iter().enumerate().skip(1) {
+        let frac = count as f64 / games as f64;
         let w1 = (30.0 * frac).round() as usize;
         let w2 = (30.0 * (1.0 - frac)).round() as usize;
         eprintln!(
@@ -133,16 +1016,81 @@ where
             count
         );
     }
-    eprintln!("average score: {:.4}", score as f64 / f64::from(games));
+    eprintln!("average score: {:.4}", score as f64 / games as f64);
 }
 
 #[cfg(test)]
 mod tests {
+    use super::Range;
+
+    /// Ranges for every parameter of a configuration, with the log scale ones above zero.
+    const RANGES: [Range; 5] = [
+        Range { min: 1.0, max: 1e8 },
+        Range {
+            min: 1e-9,
+            max: 1e-5,
+        },
+        Range { min: 0.1, max: 0.5 },
+        Range { min: 0.0, max: 2.0 },
+        Range {
+            min: -1.0,
+            max: 1.0,
+        },
+    ];
+
+    /// Whether every value of `config` is within its range, give or take rounding.
+    fn within(config: &super::Config) -> bool {
+        config.iter().zip(RANGES).all(|(&value, range)| {
+            let slack = 1e-9_f64 * range.max.abs().max(1.0_f64);
+            range.min - slack <= value && value <= range.max + slack
+        })
+    }
+
+    #[test]
+    fn ranges() {
+        let error = |s: &str| Err(format!("'{s}' should be `min,max` with min <= max"));
+        assert_eq!("0.5,2".parse(), Ok(Range { min: 0.5, max: 2.0 }));
+        assert_eq!("2,2".parse(), Ok(Range { min: 2.0, max: 2.0 }));
+        assert_eq!("2,0.5".parse::<Range>(), error("2,0.5"));
+        assert_eq!("0.5".parse::<Range>(), error("0.5"));
+        assert_eq!("a,b".parse::<Range>(), error("a,b"));
+    }
+
+    #[test]
+    fn grid_points() {
+        let points = super::grid(&RANGES, 3);
+        assert_eq!(points.len(), 243);
+        assert!(points.iter().all(within));
+
+        let middle = super::grid(&RANGES, 1);
+        assert_eq!(middle.len(), 1);
+        let [k, _, cutoff_fraction, _, _] = *middle.first().expect("there is one point");
+        assert!((cutoff_fraction - 0.3_f64).abs() < 1e-12_f64);
+        // The middle of a log scale range is its geometric mean.
+        assert!((k - 1e4_f64).abs() < 1e-6_f64);
+    }
+
+    #[test]
+    fn random_points() {
+        let points = super::random(&RANGES, 50, 7);
+        assert_eq!(points.len(), 50);
+        assert!(points.iter().all(within));
+        assert_eq!(points, super::random(&RANGES, 50, 7));
+        assert_ne!(points, super::random(&RANGES, 50, 8));
+    }
+
+    #[test]
+    fn evaluate_nothing_solved() {
+        let options = popoki::Options::default();
+        assert_eq!(super::evaluate(&options, 0), (None, 0.0_f64));
+    }
+
     #[test]
     fn default_solver() {
         let w = popoki::Wordle::new();
-        let results: Vec<_> = crate::GAMES
-            .split_whitespace()
+        let results: Vec<_> = w
+            .dictionary()
+            .answers()
             .take(20)
             .filter_map(|answer| w.play(answer, popoki::Solver::default()))
             .collect();
@@ -152,4 +1100,15 @@ mod tests {
             [3, 4, 4, 4, 4, 5, 4, 4, 3, 3, 4, 3, 4, 3, 5, 2, 3, 3, 3, 4]
         );
     }
+
+    #[test]
+    fn parallel_games() {
+        let w = popoki::Wordle::new();
+        let answers: Vec<&str> = w.dictionary().answers().take(20).collect();
+        let play = |answer: &&str| w.play(answer, popoki::Solver::default());
+        assert_eq!(
+            super::parallel(&answers, 4, play),
+            super::parallel(&answers, 1, play)
+        );
+    }
 }
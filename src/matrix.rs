@@ -0,0 +1,192 @@
+use crate::{patterns, Dictionary, Pattern};
+use memmap2::Mmap;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// The bytes that every pattern matrix file starts with.
+const MAGIC: &[u8; 8] = b"POPOKIPM";
+
+/// The version of the file format, which changes whenever the layout does.
+const VERSION: u16 = 1;
+
+/// The length of the header: the magic bytes, the version, the word length, the bytes per
+/// pattern, two bytes of padding, the number of words and the dictionary checksum.
+const HEADER: usize = 32;
+
+/// The pattern of every guess and answer of a dictionary, read from a file written by
+/// [`Matrix::write`] that is mapped into memory rather than read.
+///
+/// The file starts with a header that holds a format version and the checksum of the dictionary,
+/// followed by a row for each guess with the index of the pattern of each answer, in dictionary
+/// order. Indices take a byte for words of up to 5 letters and two little-endian bytes for longer
+/// ones. Solvers ignore a matrix that was written for another dictionary, and [`Matrix::check`]
+/// tells why.
+#[derive(Debug)]
+pub struct Matrix<const N: usize = 5> {
+    map: Mmap,
+    words: usize,
+    checksum: u64,
+}
+
+/// An error returned when a [`Matrix`] could not be opened, or does not fit a dictionary.
+#[derive(Debug)]
+pub enum MatrixError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// The file is not a pattern matrix, or it was cut short.
+    NotAMatrix,
+
+    /// The file was written in another version of the format.
+    Version(u16),
+
+    /// The file holds the patterns of words with another number of letters.
+    Letters(usize),
+
+    /// The matrix was written for another dictionary.
+    OtherDictionary,
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read pattern matrix: {}", e),
+            Self::NotAMatrix => write!(f, "the file is not a complete pattern matrix"),
+            Self::Version(version) => write!(
+                f,
+                "the pattern matrix is in version {} of the format rather than {} and must be \
+                 written again",
+                version, VERSION
+            ),
+            Self::Letters(letters) => write!(
+                f,
+                "the pattern matrix holds the patterns of {} letter words",
+                letters
+            ),
+            Self::OtherDictionary => write!(
+                f,
+                "the pattern matrix was written for another dictionary and must be written again"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MatrixError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The number of bytes each pattern index of `N` letter words takes.
+fn width<const N: usize>() -> usize {
+    if Pattern::<N>::COUNT <= usize::from(u8::MAX) {
+        1
+    } else {
+        2
+    }
+}
+
+impl<const N: usize> Matrix<N> {
+    /// Compute the pattern of every guess and answer of `dictionary` on every core, and write them
+    /// to a file at `path` that [`Matrix::open`] can map.
+    pub fn write(dictionary: &Dictionary<N>, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&(N as u16).to_le_bytes())?;
+        file.write_all(&(width::<N>() as u16).to_le_bytes())?;
+        file.write_all(&[0; 2])?;
+        file.write_all(&(dictionary.len() as u64).to_le_bytes())?;
+        file.write_all(&dictionary.checksum().to_le_bytes())?;
+
+        patterns::with(dictionary, None, |cache| {
+            cache.precompute(dictionary);
+            for (guess_idx, (guess, _)) in dictionary.words().enumerate() {
+                let row = cache.row::<N>(guess_idx);
+                for (answer_idx, (answer, _)) in dictionary.words().enumerate() {
+                    let idx = row.get::<N>(guess, answer, answer_idx);
+                    if width::<N>() == 1 {
+                        file.write_all(&[idx as u8])?;
+                    } else {
+                        file.write_all(&(idx as u16).to_le_bytes())?;
+                    }
+                }
+            }
+            Ok::<_, io::Error>(())
+        })?;
+        file.flush()
+    }
+
+    /// Map the matrix at `path` into memory, read-only.
+    ///
+    /// The file must not be changed while it is mapped.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MatrixError> {
+        let file = File::open(path)?;
+        // Safety: the map is only read, and the file is not expected to change while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map.get(..HEADER).ok_or(MatrixError::NotAMatrix)?;
+        let u16_at = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+        let u64_at = |at: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&header[at..at + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        if &header[..8] != MAGIC {
+            return Err(MatrixError::NotAMatrix);
+        }
+        if u16_at(8) != VERSION {
+            return Err(MatrixError::Version(u16_at(8)));
+        }
+        if usize::from(u16_at(10)) != N {
+            return Err(MatrixError::Letters(usize::from(u16_at(10))));
+        }
+        let words = usize::try_from(u64_at(16)).map_err(|_| MatrixError::NotAMatrix)?;
+        let cells = words
+            .checked_mul(words)
+            .and_then(|cells| cells.checked_mul(width::<N>()));
+        if usize::from(u16_at(12)) != width::<N>() || cells != Some(map.len() - HEADER) {
+            return Err(MatrixError::NotAMatrix);
+        }
+
+        Ok(Self {
+            checksum: u64_at(24),
+            words,
+            map,
+        })
+    }
+
+    /// Check that this matrix was written for `dictionary`.
+    pub fn check(&self, dictionary: &Dictionary<N>) -> Result<(), MatrixError> {
+        if self.checksum == dictionary.checksum() && self.words == dictionary.len() {
+            Ok(())
+        } else {
+            Err(MatrixError::OtherDictionary)
+        }
+    }
+
+    /// The row holding the patterns of the guess with index `guess_idx`.
+    #[inline]
+    pub(crate) fn row(&self, guess_idx: usize) -> patterns::Row<'_> {
+        let len = self.words * width::<N>();
+        let row = &self.map[HEADER + guess_idx * len..][..len];
+        if width::<N>() == 1 {
+            patterns::Row::Mapped(row)
+        } else {
+            patterns::Row::MappedWide(row)
+        }
+    }
+}
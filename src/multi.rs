@@ -0,0 +1,211 @@
+use crate::{
+    bitset::{self, Bitset},
+    solver::{candidates, set_of, Words},
+    Correctness, Error, Guess, HardMode, MultiGuess, MultiGuesser, Opener, Options, Pattern, Rank,
+};
+use std::borrow::Cow;
+
+/// A solver for games played on several boards at once, such as Dordle, Quordle or Octordle.
+///
+/// Each board keeps its own set of candidates. Guesses are ranked by the information they reveal
+/// across every board that is not yet solved, plus the chance that they solve one of those boards
+/// outright. As soon as a board is down to a single candidate, that candidate is guessed.
+///
+/// Unless `hard_mode` is [`HardMode::Easy`], only words that are still a candidate on at least one
+/// board are guessed, since the hints of different boards rarely fit a single word.
+/// Ranks other than [`Rank::First`] all use the combined information described above.
+#[derive(Clone)]
+pub struct MultiSolver<const N: usize = 5> {
+    initial: Words,
+    boards: Vec<Board>,
+    options: Options<N>,
+}
+
+#[derive(Clone)]
+struct Board {
+    remaining: Words,
+    /// The words of `remaining`, as a set.
+    set: Bitset,
+    solved: bool,
+}
+
+impl<const N: usize> Options<N> {
+    /// Build a solver for a game played on `boards` boards at once.
+    pub fn build_multi(self, boards: usize) -> MultiSolver<N> {
+        let initial = self.initial_words();
+        let remaining = candidates(&initial);
+        let board = Board {
+            set: set_of(self.dictionary.len(), &remaining),
+            remaining,
+            solved: false,
+        };
+        MultiSolver {
+            boards: vec![board; boards],
+            initial,
+            options: self,
+        }
+    }
+}
+
+impl<const N: usize> MultiSolver<N> {
+    /// The words that are still possible answers on `board`, most likely first.
+    pub fn candidates(&self, board: usize) -> impl Iterator<Item = &str> + '_ {
+        self.boards[board]
+            .remaining
+            .iter()
+            .map(|&(word_idx, _)| self.options.dictionary.word(word_idx))
+    }
+}
+
+impl<const N: usize> MultiSolver<N> {
+    /// Narrow down the candidates of every board with the feedback of the last guess of
+    /// `history`. The boards are left as they were if the feedback does not make sense.
+    fn apply(&mut self, history: &[MultiGuess<'_, N>]) -> Result<(), Error> {
+        let last = history.last().expect("history has a guess");
+        let options = &self.options;
+        let dictionary = &options.dictionary;
+        crate::check_word::<N>(&last.word)?;
+        let last_idx = dictionary
+            .index_of(&last.word)
+            .ok_or_else(|| Error::NotInDictionary(last.word.to_string()))?;
+        if last.masks.len() != self.boards.len() {
+            return Err(Error::Boards {
+                guess: history.len(),
+                expected: self.boards.len(),
+                found: last.masks.len(),
+            });
+        }
+
+        let mut boards = self.boards.clone();
+        for (b, (board, mask)) in boards.iter_mut().zip(&last.masks).enumerate() {
+            match mask {
+                Some(mask) if *mask != [Correctness::Correct; N] => {
+                    let guess = Guess {
+                        word: Cow::Borrowed(&*last.word),
+                        mask: *mask,
+                    };
+                    guess.check(dictionary, history.len())?;
+                    bitset::narrow(
+                        options,
+                        &mut board.set,
+                        &board.remaining,
+                        last_idx,
+                        Pattern(*mask),
+                    );
+                    board.remaining = board
+                        .set
+                        .iter()
+                        .map(|word_idx| self.initial[word_idx])
+                        .collect();
+                    if board.remaining.is_empty() {
+                        // Every guess so far has feedback for this board, since it is not solved.
+                        let board_history: Vec<Guess<'_, N>> = history
+                            .iter()
+                            .filter_map(|guess| {
+                                Some(Guess {
+                                    word: Cow::Borrowed(&*guess.word),
+                                    mask: (*guess.masks.get(b)?)?,
+                                })
+                            })
+                            .collect();
+                        return Err(crate::inconsistent(dictionary, &board_history));
+                    }
+                }
+                _ => board.solved = true,
+            }
+        }
+        self.boards = boards;
+        Ok(())
+    }
+}
+
+impl<const N: usize> MultiGuesser<N> for MultiSolver<N> {
+    fn guess(&mut self, history: &[MultiGuess<'_, N>]) -> String {
+        self.try_guess(history).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Only the last guess of `history` is applied, so this must be asked for a guess after every
+    /// guess of the game. Fails if that guess is not in the dictionary, if it does not have
+    /// feedback for every board, if its feedback does not make sense, or if every board is solved.
+    fn try_guess(&mut self, history: &[MultiGuess<'_, N>]) -> Result<String, Error> {
+        if !history.is_empty() {
+            self.apply(history)?;
+        }
+        let options = &self.options;
+        let dictionary = &options.dictionary;
+
+        let open: Vec<&Board> = self.boards.iter().filter(|board| !board.solved).collect();
+        if open.is_empty() {
+            return Err(Error::Solved);
+        }
+
+        // A board with a single candidate left is solved by guessing it.
+        if let Some(board) = open.iter().find(|board| board.remaining.len() == 1) {
+            return Ok(dictionary.word(board.remaining[0].0).to_string());
+        }
+        if history.is_empty() {
+            if let Opener::Word(word) = &options.opener {
+                if dictionary.contains(word) {
+                    return Ok(word.clone());
+                }
+            }
+        }
+        if options.rank_by == Rank::First {
+            return Ok(dictionary.word(open[0].remaining[0].0).to_string());
+        }
+
+        let remaining_p: Vec<f64> = open
+            .iter()
+            .map(|board| board.remaining.iter().map(|&(_, p)| p).sum())
+            .collect();
+        let mut is_candidate = Bitset::new(dictionary.len());
+        for board in &open {
+            is_candidate.union(&board.set);
+        }
+        let candidates = is_candidate.len();
+        let stop = options.cutoff_stop(candidates);
+
+        let mut best: Option<(f64, usize)> = None;
+        let mut i = 0;
+        let mut totals = vec![0.0f64; Pattern::<N>::COUNT];
+        for &(word_idx, _) in &*self.initial {
+            let in_remaining = is_candidate.contains(word_idx);
+            if options.hard_mode != HardMode::Easy && !in_remaining {
+                continue;
+            }
+
+            let mut goodness = 0.0;
+            for (board, &remaining_p) in open.iter().zip(&remaining_p) {
+                totals.fill(0.0);
+                let mut p_word = 0.0;
+                options.for_each_pattern(word_idx, &board.remaining, |&(idx, p), pattern| {
+                    if idx == word_idx {
+                        p_word = p / remaining_p;
+                    }
+                    totals[pattern] += p;
+                });
+                let e_info = -totals
+                    .iter()
+                    .filter(|t| **t != 0.0)
+                    .map(|&p| {
+                        let p_of_this_pattern = p / remaining_p;
+                        p_of_this_pattern * p_of_this_pattern.log2()
+                    })
+                    .sum::<f64>();
+                goodness += e_info + p_word;
+            }
+            if best.is_none_or(|(best, _)| goodness > best) {
+                best = Some((goodness, word_idx));
+            }
+
+            if options.cutoff && in_remaining {
+                i += 1;
+                if i >= stop {
+                    break;
+                }
+            }
+        }
+        let (_, best) = best.unwrap();
+        Ok(dictionary.word(best).to_string())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::Pattern;
+
+/// A byte of `1` in every lane.
+const ONES: u64 = 0x0101_0101_0101_0101;
+
+/// The high bit of every lane.
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+/// Words stored letter by letter, so that the patterns of a guess against all of them can be
+/// computed eight words at a time.
+///
+/// The `i`th letters of eight consecutive words are packed into the bytes of a `u64`, which lets
+/// a single integer operation compare a letter of the guess with a letter of all eight words.
+/// This works the same everywhere, without relying on the compiler to vectorise anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedWords<const N: usize = 5> {
+    /// The `i`th letters of each group of eight words.
+    letters: [Vec<u64>; N],
+    len: usize,
+}
+
+/// `1` in the lanes of `x` that are zero, and `0` in the others.
+#[inline]
+fn zero_lanes(x: u64) -> u64 {
+    // Adding 0x7f to the low 7 bits carries into the high bit unless they are all zero, and the
+    // high bit of `x` covers the rest. Nothing carries from one lane into the next.
+    (!((((x & !HIGH) + !HIGH) | x) | !HIGH) & HIGH) >> 7
+}
+
+/// `1` in the lanes where `a` is greater than `b`, and `0` in the others. Every lane of both must
+/// be below 128.
+#[inline]
+fn greater_lanes(a: u64, b: u64) -> u64 {
+    // `128 + a - b - 1` keeps its high bit exactly when `a > b`, and never borrows from the next
+    // lane.
+    (((a | HIGH) - b - ONES) & HIGH) >> 7
+}
+
+impl<const N: usize> PackedWords<N> {
+    /// Pack `words`, which must all have `N` letters.
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut letters: [Vec<u64>; N] = std::array::from_fn(|_| Vec::new());
+        let mut len = 0_usize;
+        for word in words {
+            assert_eq!(word.len(), N, "'{}' does not have {} letters", word, N);
+            let lane = len % 8;
+            for (column, &letter) in letters.iter_mut().zip(word.as_bytes()) {
+                if lane == 0 {
+                    column.push(0);
+                }
+                // Padding is left as 0, which never equals a letter.
+                *column.last_mut().unwrap() |= u64::from(letter) << (8 * lane);
+            }
+            len += 1;
+        }
+        Self { letters, len }
+    }
+
+    /// The number of words.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Replace the contents of `out` with the index of the pattern of `guess` against each word, in
+    /// order. These are exactly `Pattern(Correctness::compute(word, guess)).index()`.
+    ///
+    /// [`Correctness::compute`]: crate::Correctness::compute
+    pub fn patterns(&self, guess: &str, out: &mut Vec<usize>) {
+        assert_eq!(guess.len(), N);
+        assert!(N < 128, "letter counts must fit in 7 bits");
+        let guess = guess.as_bytes();
+        let spread: [u64; N] = std::array::from_fn(|i| u64::from(guess[i]) * ONES);
+        out.clear();
+        out.reserve(8 * self.letters.first().map_or(0, Vec::len));
+
+        // A letter of the guess is misplaced if it is not correct, and the answer has more of that
+        // letter in positions that are not correct than the guess has in earlier positions that
+        // are not correct. This is what `Correctness::compute` works out one letter at a time.
+        for chunk in 0..self.letters.first().map_or(0, Vec::len) {
+            let answer: [u64; N] = std::array::from_fn(|i| self.letters[i][chunk]);
+            let wrong: [u64; N] = std::array::from_fn(|i| zero_lanes(answer[i] ^ spread[i]) ^ ONES);
+
+            let mut digits = [0u64; N];
+            for i in 0..N {
+                let mut available = 0;
+                for k in 0..N {
+                    available += zero_lanes(answer[k] ^ spread[i]) & wrong[k];
+                }
+                let mut used = 0;
+                for j in (0..i).filter(|&j| guess[j] == guess[i]) {
+                    used += wrong[j];
+                }
+                // 0 if correct, 1 if misplaced and 2 otherwise.
+                digits[i] = (wrong[i] << 1) - (wrong[i] & greater_lanes(available, used));
+            }
+
+            if Pattern::<N>::COUNT <= 256 {
+                // The index fits in a lane, so it can be built up in every lane at once.
+                let index = digits.iter().fold(0, |index, &digit| index * 3 + digit);
+                out.extend(index.to_le_bytes().iter().map(|&idx| usize::from(idx)));
+            } else {
+                out.extend((0..8).map(|lane| {
+                    digits.iter().fold(0, |index, &digit| {
+                        index * 3 + (digit >> (8 * lane) & 0xff) as usize
+                    })
+                }));
+            }
+        }
+        out.truncate(self.len);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{Correctness, PackedCorrectness, WidePackedCorrectness};
+use std::{fmt, str::FromStr};
+
+/// The feedback the game gives for a single guess, such as `GYBBG` or 🟩🟨⬛⬛🟩.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pattern<const N: usize = 5>(pub [Correctness; N]);
+
+impl<const N: usize> Pattern<N> {
+    /// The number of distinct patterns for `N` letter words.
+    pub const COUNT: usize = 3usize.pow(N as u32);
+
+    /// The position of this pattern in `0..Self::COUNT`, treating each letter as a base-3 digit
+    /// with the first letter being the most significant.
+    pub fn index(&self) -> usize {
+        self.0.iter().fold(0, |acc, c| {
+            acc * 3
+                + match c {
+                    Correctness::Correct => 0,
+                    Correctness::Misplaced => 1,
+                    Correctness::Wrong => 2,
+                }
+        })
+    }
+
+    /// The inverse of [`Pattern::index`].
+    pub fn from_index(mut index: usize) -> Self {
+        let mut c = [Correctness::Wrong; N];
+        // The first letter is the most significant digit, so unpack from the back.
+        for c in c.iter_mut().rev() {
+            *c = match index % 3 {
+                0 => Correctness::Correct,
+                1 => Correctness::Misplaced,
+                _ => Correctness::Wrong,
+            };
+            index /= 3;
+        }
+        Self(c)
+    }
+}
+
+impl<const N: usize> From<[Correctness; N]> for Pattern<N> {
+    fn from(c: [Correctness; N]) -> Self {
+        Self(c)
+    }
+}
+
+impl<const N: usize> From<Pattern<N>> for [Correctness; N] {
+    fn from(p: Pattern<N>) -> Self {
+        p.0
+    }
+}
+
+impl<const N: usize> From<Pattern<N>> for PackedCorrectness {
+    fn from(p: Pattern<N>) -> Self {
+        Self::from(p.0)
+    }
+}
+
+impl<const N: usize> From<PackedCorrectness> for Pattern<N> {
+    fn from(packed: PackedCorrectness) -> Self {
+        Self::from_index(usize::from(u8::from(packed)))
+    }
+}
+
+impl<const N: usize> From<Pattern<N>> for WidePackedCorrectness {
+    fn from(p: Pattern<N>) -> Self {
+        Self::from(p.0)
+    }
+}
+
+impl<const N: usize> From<WidePackedCorrectness> for Pattern<N> {
+    fn from(packed: WidePackedCorrectness) -> Self {
+        Self::from_index(usize::from(u16::from(packed)))
+    }
+}
+
+/// An error returned when a string could not be parsed as a [`Pattern`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePatternError {
+    /// The pattern did not have exactly one symbol per letter.
+    Length { expected: usize, found: usize },
+
+    /// The pattern contained a symbol that does not describe a letter's correctness.
+    Symbol(char),
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length { expected, found } => {
+                write!(f, "expected {} symbols, found {}", expected, found)
+            }
+            Self::Symbol(c) => write!(f, "'{}' is not a pattern symbol", c),
+        }
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
+
+impl Correctness {
+    fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            'G' | 'g' | 'C' | 'c' | '2' | '🟩' => Some(Correctness::Correct),
+            'Y' | 'y' | 'M' | 'm' | '1' | '🟨' => Some(Correctness::Misplaced),
+            'B' | 'b' | 'W' | 'w' | '0' | '⬛' | '⬜' => Some(Correctness::Wrong),
+            _ => None,
+        }
+    }
+}
+
+/// Patterns can be written with one symbol per letter, using any of:
+///
+/// - `G`, `Y` and `B` for green, yellow and black,
+/// - `C`, `M` and `W` for correct, misplaced and wrong,
+/// - 🟩, 🟨 and ⬛ or ⬜, as shared by the game,
+/// - `2`, `1` and `0`.
+impl<const N: usize> FromStr for Pattern<N> {
+    type Err = ParsePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let len = s.chars().count();
+        if len != N {
+            return Err(ParsePatternError::Length {
+                expected: N,
+                found: len,
+            });
+        }
+
+        let mut c = [Correctness::Wrong; N];
+        for (symbol, c) in s.chars().zip(c.iter_mut()) {
+            *c = Correctness::from_symbol(symbol).ok_or(ParsePatternError::Symbol(symbol))?;
+        }
+        Ok(Self(c))
+    }
+}
+
+/// Formats as `GYBBG`, or as 🟩🟨⬛⬛🟩 with the alternate flag (`{:#}`).
+impl<const N: usize> fmt::Display for Pattern<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0 {
+            let symbol = match (c, f.alternate()) {
+                (Correctness::Correct, false) => 'G',
+                (Correctness::Misplaced, false) => 'Y',
+                (Correctness::Wrong, false) => 'B',
+                (Correctness::Correct, true) => '🟩',
+                (Correctness::Misplaced, true) => '🟨',
+                (Correctness::Wrong, true) => '⬛',
+            };
+            write!(f, "{}", symbol)?;
+        }
+        Ok(())
+    }
+}
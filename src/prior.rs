@@ -0,0 +1,89 @@
+use crate::Dictionary;
+
+/// How likely each word of the dictionary is to be the answer before any guess is made.
+///
+/// Only the weights of words relative to each other matter, so they need not add up to 1. Words
+/// with a weight of 0 are never considered to be the answer, but may still be guessed. Only
+/// [`Prior::Answers`] rules words out like that: the other priors keep every word possible, if
+/// only barely.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Prior {
+    /// `1 / (1 + e^(-k * (p - x0)))` of each word's frequency `p`, which treats common words as
+    /// about equally likely, and rare words as all but impossible. Words with a frequency of
+    /// around `x0` are half as likely as common words, and `k` sets how sharp the cut is.
+    Sigmoid { k: f64, x0: f64 },
+
+    /// Every word is equally likely
+    Uniform,
+
+    /// Each word is as likely as it is frequent
+    Frequency,
+
+    /// `1 / n` for the `n`th most frequent word
+    ByRank,
+
+    /// Each word's frequency raised to `exponent`, which flattens the frequencies for exponents
+    /// below 1
+    PowerLaw { exponent: f64 },
+
+    /// Only the dictionary's answers, which are equally likely. Dictionaries without answers fall
+    /// back to [`Prior::Uniform`].
+    Answers,
+}
+
+impl Prior {
+    /// The sigmoid that was found to fit the embedded dictionary and answers best.
+    pub const SIGMOID: Self = Self::Sigmoid {
+        k: 30_000_000.0,
+        x0: 0.000_004_97,
+    };
+}
+
+impl Default for Prior {
+    fn default() -> Self {
+        Self::SIGMOID
+    }
+}
+
+impl Prior {
+    /// The weight of each word of `dictionary`, in the dictionary's order.
+    pub fn weights<const N: usize>(&self, dictionary: &Dictionary<N>) -> Vec<f64> {
+        let mut weights = self.raw_weights(dictionary);
+        if !matches!(self, Self::Answers) {
+            // A sharp sigmoid or a large exponent rounds the weights of rare words down to 0.
+            for weight in &mut weights {
+                *weight = weight.max(f64::MIN_POSITIVE);
+            }
+        }
+        weights
+    }
+
+    fn raw_weights<const N: usize>(&self, dictionary: &Dictionary<N>) -> Vec<f64> {
+        let sum: usize = dictionary.words().map(|(_, count)| count).sum();
+        let frequency = |count: usize| count as f64 / sum as f64;
+        match *self {
+            Self::Sigmoid { k, x0 } => dictionary
+                .words()
+                .map(|(_, count)| 1.0 / (1.0 + (-k * (frequency(count) - x0)).exp()))
+                .collect(),
+            Self::Frequency => dictionary
+                .words()
+                .map(|(_, count)| frequency(count))
+                .collect(),
+            Self::ByRank => (1..=dictionary.len()).map(|n| 1.0 / n as f64).collect(),
+            Self::PowerLaw { exponent } => dictionary
+                .words()
+                .map(|(_, count)| frequency(count).powf(exponent))
+                .collect(),
+            Self::Answers if dictionary.answers().next().is_some() => {
+                let mut weights = vec![0.0; dictionary.len()];
+                for answer in dictionary.answers() {
+                    weights[dictionary.index_of(answer).expect("answers are words")] = 1.0;
+                }
+                weights
+            }
+            Self::Uniform | Self::Answers => vec![1.0; dictionary.len()],
+        }
+    }
+}
@@ -1,31 +1,59 @@
-use crate::{Correctness, Guess, Guesser, PackedCorrectness, DICTIONARY, MAX_MASK_ENUM};
+use crate::{
+    bitset::{self, Bitset},
+    dictionary::fnv1a,
+    endgame,
+    patterns::{self, Row},
+    Book, Correctness, Dictionary, Error, Estimator, Guess, Guesser, Matrix, PackedWords, Pattern,
+    Prior,
+};
 use once_cell::sync::OnceCell;
-use once_cell::unsync::OnceCell as UnSyncOnceCell;
-use std::{borrow::Cow, cell::Cell};
-
-/// The initial set of words after applying sigmoid smoothing
-static INITIAL_SIGMOID: OnceCell<Vec<(&'static str, f64, usize)>> = OnceCell::new();
-
-/// A per-thread cache of cached `Correctness` for each word pair.
-///
-/// Popoki make this thread-local so that access to it is as cheap as we can get it.
-///
-/// We store a `Box` because the array is quite large, and we are unlikely to have
-/// the stack space needed to store the whole thing on a giver thread's stack.
-type Cache = [[Cell<Option<PackedCorrectness>>; DICTIONARY.len()]; DICTIONARY.len()];
-thread_local! {
-    static COMPUTES: UnSyncOnceCell<Box<Cache>> = Default::default();
-}
-pub struct Solver {
-    remaining: Cow<'static, Vec<(&'static str, f64, usize)>>,
-    entropy: Vec<f64>,
-    options: Options,
-    last_guess_idx: Option<usize>,
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+/// The index of each word in the dictionary along with its weight under the prior.
+pub(crate) type Words = Arc<[(usize, f64)]>;
+
+/// The initial set of words of each dictionary after weighing them by a prior, keyed by the
+/// checksums of the dictionary and of its answers, and a hash of the prior. These are freed along
+/// with the last solver that holds them.
+static INITIAL: OnceCell<Mutex<HashMap<Key, WeakWords>>> = OnceCell::new();
+
+type Key = (u64, u64, u64);
+
+type WeakWords = Weak<[(usize, f64)]>;
+
+#[derive(Clone)]
+pub struct Solver<const N: usize = 5> {
+    initial: Words,
+    /// The words of `initial` that may be the answer before any guess is made.
+    candidates: Words,
+    remaining: Words,
+    /// The words of `remaining`, as a set.
+    remaining_set: Bitset,
+    /// The number of guesses made and the entropy of the candidates each time a guess was ranked.
+    entropy: Vec<(usize, f64)>,
+    /// How many guesses were evaluated each time guesses were ranked.
+    evaluated: Vec<usize>,
+    /// The words that may be guessed under [`HardMode::Hard`].
+    allowed: Words,
+    options: Options<N>,
+    /// The index and feedback of each guess that the candidates were narrowed down with.
+    seen: Vec<(usize, [Correctness; N])>,
+    /// The candidate sets that the endgame search has solved so far.
+    endgames: endgame::Memo,
+    /// The opening book of the options, unless it was generated for something else.
+    book: Option<Arc<Book<N>>>,
 }
 
+/// What [`Rank::Minimax`] adds to the goodness of a candidate. It is far below any difference in
+/// the chance of the largest pattern that is not down to rounding, so it only breaks ties.
+const MINIMAX_TIE: f64 = 1e-12;
+
 #[derive(Debug, Copy, Clone)]
 struct Candidate {
-    word: &'static str,
     goodness: f64,
     idx: usize,
 }
@@ -36,23 +64,6 @@ impl Default for Solver {
     }
 }
 
-// This is an estimation function for how many _more_ guesses are needed given that
-// `entropy` remains. It was constructed by iterative regression.
-fn est_steps_left(entropy: f64) -> f64 {
-    (entropy * 3.870 + 3.679).ln()
-}
-
-const PRINT_ESTIMATION: bool = false;
-
-const L: f64 = 1.0;
-const K: f64 = 30_000_000.0;
-const X0: f64 = 0.000_004_97;
-
-fn sigmoid(p: f64) -> f64 {
-    L / (1.0 + (-K * (p - X0)).exp())
-}
-const PRINT_SIGMOID: bool = false;
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Rank {
@@ -70,282 +81,858 @@ pub enum Rank {
 
     /// E[information]
     ExpectedInformation,
+
+    /// -p(largest pattern), which limits the worst case rather than the average. The pattern that
+    /// solves the game is left out, and candidates are preferred on ties.
+    Minimax,
+
+    /// The number of patterns that some candidate produces, preferring candidates on ties
+    MostParts,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Which words the solver may guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
-pub struct Options {
-    /// If true, counts will be smoothed using a sigmoid
-    pub sigmoid: bool,
+pub enum HardMode {
+    /// Any word in the dictionary
+    Easy,
+
+    /// Any word that uses every hint revealed so far, following the game's hard mode rules
+    Hard,
+
+    /// Only words that may still be the answer, which is stricter than hard mode
+    Strict,
+}
+
+/// What the solver guesses first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Opener {
+    /// Always open with this word, if it is in the dictionary. Otherwise the opener is computed.
+    Word(String),
+
+    /// Search for the best opener like any other guess, which takes a while for large dictionaries
+    Compute,
+}
+
+impl Default for Opener {
+    fn default() -> Self {
+        Self::Word("trace".to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Options<const N: usize = 5> {
+    /// How likely each word is to be the answer before any guess is made
+    pub prior: Prior,
 
     /// If true, candidates will be ranked based on expected score
     pub rank_by: Rank,
 
-    /// If true, correctness computation will be cached
+    /// If true, correctness computation will be cached. The cache of each dictionary is shared
+    /// by every solver on every thread.
     pub cache: bool,
 
-    /// If true, only the most likely 1/3 of candidates are considered at each step.
+    /// If set, the cache only keeps the patterns of this many guesses, picked from those that are
+    /// used more than once, and computes the patterns of other guesses every time. Each guess
+    /// takes a row of as many bytes as there are words in the dictionary.
+    pub cache_rows: Option<usize>,
+
+    /// If true, the cache is filled in parallel when the first solver is built, rather than as the
+    /// patterns are needed. With `cache_rows`, only the rows of the most frequent words are.
+    pub precompute: bool,
+
+    /// If true, only the most likely `cutoff_fraction` of candidates are considered at each step.
     pub cutoff: bool,
 
-    /// If true, solver may not guess known-wrong words
-    pub hard_mode: bool,
+    /// The fraction of candidates that are considered at each step if `cutoff` is set
+    pub cutoff_fraction: f64,
+
+    /// The fewest candidates that are considered at each step if `cutoff` is set
+    pub cutoff_floor: usize,
+
+    /// If true, stop considering guesses once a bound shows that none of the rest can be better
+    /// than the best one so far. Unlike `cutoff`, this never changes which guess is made, so it
+    /// is best combined with `cutoff` set to false. It does not apply to the lookahead.
+    pub adaptive_cutoff: bool,
+
+    /// Which words the solver may guess
+    pub hard_mode: HardMode,
+
+    /// Once this many candidates or fewer remain, every guess is tried at every step to find the
+    /// one that minimises the expected number of guesses left. 0 disables this search.
+    pub endgame: usize,
+
+    /// If set, the endgame search prefers strategies that never take more guesses than this
+    pub max_guesses: Option<usize>,
+
+    /// How many guesses to look ahead. Beyond 1, the `lookahead_width` best guesses by `rank_by`
+    /// are compared by their expected score that many guesses ahead
+    pub lookahead_depth: usize,
+
+    /// How many guesses to look further ahead from at each step of the lookahead
+    pub lookahead_width: usize,
+
+    /// The first guess
+    pub opener: Opener,
+
+    /// How many more guesses are estimated to be needed given the entropy that remains
+    pub estimator: Estimator,
+
+    /// An opening book to take the first two guesses from, if it was generated for these options
+    pub book: Option<Arc<Book<N>>>,
+
+    /// If set, guesses are evaluated from the most likely down, and the best one found when this
+    /// much time has passed since the solver was asked for a guess is made. Guesses are evaluated
+    /// past that until one of them is worth making under [`Options::rank_by`]. The endgame search
+    /// and the lookahead are not cut short, so they are best left off when every guess must be
+    /// made in time.
+    pub time_budget: Option<Duration>,
+
+    /// A pattern matrix made by [`Matrix::write`] to look patterns up in rather than computing or
+    /// caching them, which is ignored if it was written for another dictionary
+    pub matrix: Option<Arc<Matrix<N>>>,
+
+    /// The words the solver may guess, and that it considers as answers
+    pub dictionary: Arc<Dictionary<N>>,
 }
 
 impl Default for Options {
     fn default() -> Self {
+        Self::with_dictionary(Dictionary::embedded())
+    }
+}
+
+impl Options {
+    /// The default options for the embedded five letter words, the same as `Options::default()`.
+    ///
+    /// ```
+    /// use popoki::{Options, Rank, Wordle};
+    ///
+    /// let mut options = Options::new();
+    /// options.rank_by = Rank::Minimax;
+    /// assert!(Wordle::new().play("cigar", options.build()).is_some());
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<const N: usize> Options<N> {
+    /// The default options for playing with `dictionary`, which is how the options for words of
+    /// other lengths than five are made.
+    pub fn with_dictionary(dictionary: Arc<Dictionary<N>>) -> Self {
         Self {
-            sigmoid: true,
+            dictionary,
+            prior: Prior::default(),
             rank_by: Rank::ExpectedScore,
             cache: true,
+            cache_rows: None,
+            precompute: false,
             cutoff: true,
-            hard_mode: true,
+            cutoff_fraction: 1.0 / 3.0,
+            cutoff_floor: 20,
+            adaptive_cutoff: false,
+            time_budget: None,
+            hard_mode: HardMode::Strict,
+            endgame: 0,
+            max_guesses: None,
+            lookahead_depth: 1,
+            lookahead_width: 10,
+            opener: Opener::default(),
+            estimator: Estimator::default(),
+            book: None,
+            matrix: None,
         }
     }
 }
 
-impl Options {
-    pub fn build(self) -> Solver {
-        let remaining = INITIAL_SIGMOID.get_or_init(|| {
-            let sum: usize = DICTIONARY.iter().map(|(_, count)| count).sum();
-
-            if PRINT_SIGMOID {
-                for &(word, count) in DICTIONARY.iter().rev() {
-                    let p = count as f64 / sum as f64;
-                    println!(
-                        "{} {:.6}% -> {:.6}% ({})",
-                        word,
-                        100.0 * p,
-                        100.0 * sigmoid(p),
-                        count
-                    );
-                }
-            }
+/// Every word of `dictionary` with its weight under `prior`, which are only weighed again once no
+/// solver holds them.
+fn initial_words<const N: usize>(dictionary: &Dictionary<N>, prior: &Prior) -> Words {
+    let mut initial = INITIAL.get_or_init(Default::default).lock().unwrap();
+    let key = (
+        dictionary.checksum(),
+        dictionary.answers_checksum(),
+        fnv1a(format!("{:?}", prior).bytes()),
+    );
+    if let Some(words) = initial.get(&key).and_then(Weak::upgrade) {
+        return words;
+    }
+    initial.retain(|_, words| words.strong_count() > 0);
+    let words: Words = prior.weights(dictionary).into_iter().enumerate().collect();
+    initial.insert(key, Arc::downgrade(&words));
+    words
+}
 
-            DICTIONARY
-                .iter()
-                .copied()
-                .enumerate()
-                .map(|(idx, (word, count))| (word, sigmoid(count as f64 / sum as f64), idx))
-                .collect()
-        });
+/// The words of `initial` that may be the answer, which are those with a weight.
+pub(crate) fn candidates(initial: &Words) -> Words {
+    if initial.iter().all(|&(_, p)| p > 0.0) {
+        Arc::clone(initial)
+    } else {
+        initial.iter().filter(|&&(_, p)| p > 0.0).copied().collect()
+    }
+}
 
-        if self.cache {
-            COMPUTES.with(|c| {
-                c.get_or_init(|| {
-                    let c = &Cell::new(None::<PackedCorrectness>);
-                    assert_eq!(std::mem::size_of_val(c), 1);
-                    let c = c as *const _;
-                    let c = c as *const u8;
-                    assert_eq!(unsafe { *c }, 0);
-
-                    let mem = unsafe {
-                        std::alloc::alloc_zeroed(
-                            std::alloc::Layout::from_size_align(
-                                std::mem::size_of::<Cache>(),
-                                std::mem::align_of::<Cache>(),
-                            )
-                            .unwrap(),
-                        )
-                    };
+/// The indices of `words` as a set, for a dictionary of `len` words.
+pub(crate) fn set_of(len: usize, words: &[(usize, f64)]) -> Bitset {
+    Bitset::with_indices(len, words.iter().map(|&(word_idx, _)| word_idx))
+}
 
-                    unsafe { Box::from_raw(mem as *mut _) }
-                });
-            });
-        }
+impl<const N: usize> Options<N> {
+    pub fn build(self) -> Solver<N> {
+        let initial = self.initial_words();
+        let candidates = candidates(&initial);
+        let book = self
+            .book
+            .as_ref()
+            .filter(|book| book.check(&self).is_ok())
+            .cloned();
         Solver {
-            remaining: Cow::Borrowed(remaining),
+            book,
+            remaining_set: set_of(self.dictionary.len(), &candidates),
+            remaining: Arc::clone(&candidates),
+            candidates,
+            allowed: Arc::clone(&initial),
+            initial,
             entropy: Vec::new(),
-            last_guess_idx: None,
+            evaluated: Vec::new(),
+            seen: Vec::new(),
+            endgames: endgame::Memo::new(),
             options: self,
         }
     }
 }
 
-// This inline gives about 13% speedup.
-#[inline]
-fn get_packed(
-    row: &[Cell<Option<PackedCorrectness>>],
-    guess: &str,
-    answer: &str,
-    answer_idx: usize,
-) -> PackedCorrectness {
-    let cell = &row[answer_idx];
-    match cell.get() {
-        Some(a) => a,
-        None => {
-            let correctness = PackedCorrectness::from(Correctness::compute(answer, guess));
-            cell.set(Some(correctness));
-            correctness
+impl<const N: usize> Options<N> {
+    /// A hash of the options that change which guesses the solver makes, which leaves out the
+    /// dictionary, the cache, the pattern matrix and the opening book.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let options = format!(
+            "{:?}",
+            (
+                (self.prior, self.rank_by, &self.opener, &self.estimator),
+                (
+                    self.cutoff,
+                    self.cutoff_fraction,
+                    self.cutoff_floor,
+                    self.time_budget,
+                ),
+                (
+                    self.hard_mode,
+                    self.endgame,
+                    self.max_guesses,
+                    self.lookahead_depth,
+                    self.lookahead_width,
+                ),
+            )
+        );
+        fnv1a(options.bytes())
+    }
+
+    /// How many of `candidates` candidates are considered before the cutoff, if it is enabled.
+    pub(crate) fn cutoff_stop(&self, candidates: usize) -> usize {
+        ((candidates as f64 * self.cutoff_fraction) as usize)
+            .max(self.cutoff_floor)
+            .min(candidates)
+    }
+
+    /// Fill the pattern cache up front if that is enabled, and return every word of the
+    /// dictionary with its weight under the prior.
+    pub(crate) fn initial_words(&self) -> Words {
+        if self.cache && self.precompute && self.matrix.is_none() {
+            patterns::with(&self.dictionary, self.cache_rows, |cache| {
+                cache.precompute(&self.dictionary);
+            });
+        }
+        initial_words(&self.dictionary, &self.prior)
+    }
+
+    /// Call `f` with the row of patterns of the guess with index `guess_idx`, from the pattern
+    /// matrix if it was written for the dictionary, or else from the cache if it is enabled.
+    #[inline]
+    pub(crate) fn with_row<T>(
+        &self,
+        guess_idx: usize,
+        f: impl FnOnce(patterns::Row<'_>) -> T,
+    ) -> T {
+        let dictionary = &self.dictionary;
+        match &self.matrix {
+            Some(matrix) if matrix.check(dictionary).is_ok() => f(matrix.row(guess_idx)),
+            _ if self.cache => patterns::with(dictionary, self.cache_rows, |cache| {
+                f(cache.row::<N>(guess_idx))
+            }),
+            _ => f(patterns::Row::Uncached),
         }
     }
+
+    /// Call `f` with each of `answers` and the index of the pattern seen when guessing the word
+    /// with index `guess_idx` if that is the answer.
+    #[inline]
+    pub(crate) fn for_each_pattern(
+        &self,
+        guess_idx: usize,
+        answers: &[(usize, f64)],
+        mut f: impl FnMut(&(usize, f64), usize),
+    ) {
+        let dictionary = &self.dictionary;
+        let guess = dictionary.word(guess_idx);
+        self.with_row(guess_idx, |row| {
+            for answer in answers {
+                f(
+                    answer,
+                    row.get::<N>(guess, dictionary.word(answer.0), answer.0),
+                );
+            }
+        });
+    }
 }
 
 impl Solver {
+    /// A solver with the default options for the embedded five letter words.
+    ///
+    /// ```
+    /// use popoki::{Solver, Wordle};
+    ///
+    /// assert!(Wordle::new().play("cigar", Solver::new()).is_some());
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     pub fn builder() -> Options {
-        Options::default()
+        Options::new()
     }
 }
 
-impl Solver {
-    fn trim(&mut self, mut cmp: impl FnMut(&str, usize) -> bool) {
-        if matches!(self.remaining, Cow::Owned(_)) {
-            self.remaining
-                .to_mut()
-                .retain(|&(word, _, word_idx)| cmp(word, word_idx));
-        } else {
-            self.remaining = Cow::Owned(
-                self.remaining
-                    .iter()
-                    .filter(|(word, _, word_idx)| cmp(word, *word_idx))
-                    .copied()
-                    .collect(),
-            );
+impl<const N: usize> Solver<N> {
+    /// The words that are still possible answers, most likely first.
+    pub fn candidates(&self) -> impl Iterator<Item = &str> + '_ {
+        self.remaining
+            .iter()
+            .map(|&(word_idx, _)| self.options.dictionary.word(word_idx))
+    }
+}
+
+impl<const N: usize> Solver<N> {
+    /// Narrow down the candidates with the feedback of the last guess of `history`.
+    fn apply(&mut self, history: &[Guess<'_, N>]) -> Result<(), Error> {
+        let last = history.last().expect("history has a guess");
+        let dictionary = Arc::clone(&self.options.dictionary);
+        last.check(&dictionary, history.len())?;
+        let last_idx = dictionary
+            .index_of(&last.word)
+            .expect("guess is in the dictionary");
+
+        bitset::narrow(
+            &self.options,
+            &mut self.remaining_set,
+            &self.remaining,
+            last_idx,
+            Pattern(last.mask),
+        );
+        self.remaining = self
+            .remaining_set
+            .iter()
+            .map(|word_idx| self.initial[word_idx])
+            .collect();
+        if self.remaining.is_empty() {
+            return Err(crate::inconsistent(&dictionary, history));
         }
+
+        if self.options.hard_mode == HardMode::Hard {
+            self.allowed = self
+                .allowed
+                .iter()
+                .filter(|&&(word_idx, _)| {
+                    last.check_hard_mode(dictionary.word(word_idx)) == Ok(Ok(()))
+                })
+                .copied()
+                .collect();
+        }
+        self.seen.push((last_idx, last.mask));
+        Ok(())
     }
 }
 
-impl Guesser for Solver {
-    fn guess(&mut self, history: &[Guess]) -> String {
+impl<const N: usize> Solver<N> {
+    /// Like [`Guesser::guess`], but fails instead of panicking if a guess of `history` is not in
+    /// the dictionary, if its feedback does not make sense, or if no word could be the answer.
+    ///
+    /// `history` need not come from this solver. Guesses it has not seen yet are applied in
+    /// order, and if `history` does not carry on from the guesses it has seen, it starts over.
+    /// The solver is left as it was when this fails, so it can be given a corrected history.
+    pub fn try_guess(&mut self, history: &[Guess<'_, N>]) -> Result<String, Error> {
+        let deadline = self
+            .options
+            .time_budget
+            .map(|budget| Instant::now() + budget);
         let score = history.len() as f64;
 
-        if let Some(last) = history.last() {
-            if self.options.cache {
-                let reference = PackedCorrectness::from(last.mask);
-                COMPUTES.with(|c| {
-                    let row = &c.get().unwrap()[self.last_guess_idx.unwrap()];
-                    self.trim(|word, word_idx| {
-                        reference == get_packed(row, &last.word, word, word_idx)
-                    });
-                });
-            } else {
-                self.trim(|word, _| last.matches(word));
+        self.catch_up(history)?;
+
+        if let Some(book) = &self.book {
+            match history {
+                [] => return Ok(book.opener().to_string()),
+                [first] if first.word == book.opener() => {
+                    if let Some(second) = book.second(Pattern(first.mask)) {
+                        return Ok(second.to_string());
+                    }
+                }
+                _ => {}
             }
         }
-
         if history.is_empty() {
-            self.last_guess_idx = Some(
-                self.remaining
-                    .iter()
-                    .find(|(word, _, _)| &**word == "trace")
-                    .map(|&(_, _, idx)| idx)
-                    .unwrap(),
-            );
-            return "trace".to_string();
-        } else if self.options.rank_by == Rank::First || self.remaining.len() == 1 {
-            let w = self.remaining.first().unwrap();
-            self.last_guess_idx = Some(w.2);
-            return w.0.to_string();
+            // An opener that is not in the dictionary, such as one for another word length, is
+            // searched for below instead.
+            if let Opener::Word(word) = &self.options.opener {
+                if self.options.dictionary.contains(word) {
+                    return Ok(word.clone());
+                }
+            }
+        }
+        let first = match self.remaining.first() {
+            Some(&(first, _)) => first,
+            None => return Err(Error::Empty),
+        };
+        if self.options.rank_by == Rank::First || self.remaining.len() == 1 {
+            return Ok(self.options.dictionary.word(first).to_string());
+        }
+
+        if self.remaining.len() <= self.options.endgame {
+            let guesses_left = self
+                .options
+                .max_guesses
+                .map(|max| max.saturating_sub(history.len()));
+            let (_, best) = endgame::best_guess(
+                &self.options,
+                &self.remaining,
+                guesses_left,
+                &mut self.endgames,
+            )
+            .or_else(|| {
+                endgame::best_guess(&self.options, &self.remaining, None, &mut self.endgames)
+            })
+            .expect("without a limit there is always a best guess");
+            return Ok(self.options.dictionary.word(best).to_string());
+        }
+
+        let remaining_entropy = self.remaining_entropy();
+        self.entropy.push((history.len(), remaining_entropy));
+
+        let mut ranked = self.rank(score, remaining_entropy, true, deadline);
+        self.evaluated.push(ranked.len());
+        let best = ranked
+            .iter()
+            .copied()
+            .reduce(|best, c| if c.goodness > best.goodness { c } else { best })
+            .unwrap();
+        assert_ne!(best.goodness, 0.0);
+
+        let dictionary = &self.options.dictionary;
+        if self.options.lookahead_depth > 1 {
+            ranked.sort_by(|a, b| b.goodness.total_cmp(&a.goodness));
+            self.look_ahead(&mut ranked, score);
+            return Ok(dictionary.word(ranked[0].idx).to_string());
         }
+        Ok(dictionary.word(best.idx).to_string())
+    }
+
+    /// The entropy of the candidates each time this solver ranked guesses in a game that took
+    /// `guesses` guesses, with the number of guesses that were still needed from there.
+    ///
+    /// These are what [`Estimator::fit`] fits an estimator to.
+    pub fn samples(&self, guesses: usize) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.entropy
+            .iter()
+            .map(move |&(made, entropy)| (entropy, guesses - made))
+    }
+
+    /// How many guesses were evaluated each time this solver ranked guesses, in order. A guess that
+    /// is made without ranking, such as the opener or the last candidate, adds nothing.
+    ///
+    /// With a [`Options::time_budget`], this is how many the solver managed to evaluate in time.
+    pub fn evaluated(&self) -> &[usize] {
+        &self.evaluated
+    }
 
-        assert!(!self.remaining.is_empty());
+    /// Every guess that the solver would consider after `history`, best first, with its goodness
+    /// under [`Options::rank_by`]. Unlike [`Solver::try_guess`], no guesses are cut off.
+    ///
+    /// With a [`Options::lookahead_depth`] above one, the first [`Options::lookahead_width`]
+    /// guesses are in the order the lookahead puts them in, which may not be that of their
+    /// goodness.
+    ///
+    /// `history` is applied as by [`Solver::try_guess`].
+    pub fn rank_guesses(&mut self, history: &[Guess<'_, N>]) -> Result<Vec<(String, f64)>, Error> {
+        self.catch_up(history)?;
+        let mut ranked = self.rank(history.len() as f64, self.remaining_entropy(), false, None);
+        // A stable sort, so that ties keep the order in which the guesses were considered.
+        ranked.sort_by(|a, b| b.goodness.total_cmp(&a.goodness));
+        self.look_ahead(&mut ranked, history.len() as f64);
+        let dictionary = &self.options.dictionary;
+        Ok(ranked
+            .into_iter()
+            .map(|c| (dictionary.word(c.idx).to_string(), c.goodness))
+            .collect())
+    }
 
-        let remaining_p: f64 = self.remaining.iter().map(|&(_, p, _)| p).sum();
-        let remaining_entropy = -self
+    /// Apply the guesses of `history` that have not been applied yet, starting over if `history`
+    /// does not carry on from the guesses seen so far. The solver is left as it was on failure.
+    fn catch_up(&mut self, history: &[Guess<'_, N>]) -> Result<(), Error> {
+        // Start over if `history` does not carry on from the guesses applied so far, such as when
+        // it comes from another game or a guess was taken back.
+        let dictionary = Arc::clone(&self.options.dictionary);
+        let carries_on = self.seen.len() <= history.len()
+            && self.seen.iter().zip(history).all(|(&(idx, mask), guess)| {
+                dictionary.word(idx) == guess.word && mask == guess.mask
+            });
+        let snapshot = (
+            Arc::clone(&self.remaining),
+            self.remaining_set.clone(),
+            Arc::clone(&self.allowed),
+            self.seen.clone(),
+        );
+        if !carries_on {
+            self.remaining = Arc::clone(&self.candidates);
+            self.remaining_set = set_of(dictionary.len(), &self.candidates);
+            self.allowed = Arc::clone(&self.initial);
+            self.seen.clear();
+        }
+        for i in self.seen.len()..history.len() {
+            if let Err(e) = self.apply(&history[..=i]) {
+                (self.remaining, self.remaining_set, self.allowed, self.seen) = snapshot;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// The entropy of the candidates that remain.
+    fn remaining_entropy(&self) -> f64 {
+        let remaining_p: f64 = self.remaining.iter().map(|&(_, p)| p).sum();
+        -self
             .remaining
             .iter()
-            .map(|&(_, p, _)| {
+            .map(|&(_, p)| {
                 let p = p / remaining_p;
                 p * p.log2()
             })
-            .sum::<f64>();
-        self.entropy.push(remaining_entropy);
+            .sum::<f64>()
+    }
 
-        let mut best: Option<Candidate> = None;
+    /// The goodness of each guess worth considering after `score` guesses, in the order they were
+    /// considered. With `prune`, guesses are skipped as set by the cutoff options. With a
+    /// `deadline`, the most likely guesses are considered first, and no more once it passes and
+    /// one of them has a goodness other than zero.
+    fn rank(
+        &self,
+        score: f64,
+        remaining_entropy: f64,
+        prune: bool,
+        deadline: Option<Instant>,
+    ) -> Vec<Candidate> {
+        let cutoff = prune && self.options.cutoff;
+        let adaptive = prune && self.options.adaptive_cutoff && self.options.lookahead_depth <= 1;
+        let remaining_p: f64 = self.remaining.iter().map(|&(_, p)| p).sum();
+        let mut ranked = Vec::new();
         let mut i = 0;
-        let stop = (self.remaining.len() / 3).max(20).min(self.remaining.len());
-        let consider = if self.options.hard_mode {
-            &*self.remaining
+        let stop = self.options.cutoff_stop(self.remaining.len());
+        let consider = match self.options.hard_mode {
+            HardMode::Easy => &*self.initial,
+            HardMode::Hard => &*self.allowed,
+            HardMode::Strict => &*self.remaining,
+        };
+        let by_prior: Vec<(usize, f64)>;
+        let consider = if deadline.is_some() {
+            // The most likely guesses first, so that they are evaluated before the deadline.
+            let mut sorted = consider.to_vec();
+            sorted.sort_by(|a, b| b.1.total_cmp(&a.1));
+            by_prior = sorted;
+            &by_prior
         } else {
-            INITIAL_SIGMOID.get().unwrap()
+            consider
         };
+        let dictionary = &self.options.dictionary;
+        let mut totals = vec![0.0f64; Pattern::<N>::COUNT];
+        let solved = Pattern([Correctness::Correct; N]).index();
+
+        // The largest weight of any guess from each one on, which bounds the chance that any of
+        // them is the answer.
+        let mut largest_from = Vec::new();
+        if adaptive {
+            largest_from = consider
+                .iter()
+                .rev()
+                .scan(0.0f64, |largest, &(_, p)| {
+                    *largest = largest.max(p);
+                    Some(*largest)
+                })
+                .collect();
+            largest_from.reverse();
+        }
+        let mut best = f64::NEG_INFINITY;
+        let mut informative = false;
+        let packed = once_cell::unsync::OnceCell::<PackedWords<N>>::new();
+        let mut patterns = Vec::new();
 
-        for &(word, count, word_idx) in consider {
+        for (position, &(word_idx, count)) in consider.iter().enumerate() {
+            let word = dictionary.word(word_idx);
             // Rather than iterate over the patterns sequentially and add up the counts of words
             // that result in that pattern, Popoki can instead keep a running total for each pattern
             // simultaneously by storing them in an array. It can do this since each candidate-word
             // pair deterministically produces only one mask.
 
-            let mut totals = [0.0f64; MAX_MASK_ENUM];
+            totals.fill(0.0);
 
-            let mut in_remaining = false;
-            if self.options.cache {
-                COMPUTES.with(|c| {
-                    let row = &c.get().unwrap()[word_idx];
-                    for (candidate, count, candidate_idx) in &*self.remaining {
-                        in_remaining |= word_idx == *candidate_idx;
-                        let idx = get_packed(row, word, candidate, *candidate_idx);
-                        totals[usize::from(u8::from(idx))] += count;
+            let in_remaining = self.remaining_set.contains(word_idx);
+            self.options.with_row(word_idx, |row| {
+                if let Row::Uncached = row {
+                    // Patterns that are not cached are computed for every candidate at once.
+                    let packed = packed.get_or_init(|| {
+                        PackedWords::new(
+                            self.remaining.iter().map(|&(idx, _)| dictionary.word(idx)),
+                        )
+                    });
+                    packed.patterns(word, &mut patterns);
+                    for (&(_, count), &idx) in self.remaining.iter().zip(&patterns) {
+                        totals[idx] += count;
+                    }
+                } else {
+                    for &(candidate_idx, count) in &*self.remaining {
+                        let candidate = dictionary.word(candidate_idx);
+                        let idx = row.get::<N>(word, candidate, candidate_idx);
+                        totals[idx] += count;
                     }
-                });
-            } else {
-                for (candidate, count, candidate_idx) in &*self.remaining {
-                    in_remaining |= word_idx == *candidate_idx;
-                    let idx = PackedCorrectness::from(Correctness::compute(candidate, word));
-                    totals[usize::from(u8::from(idx))] += count;
                 }
-            }
+            });
 
             let sum: f64 = totals
-                .into_iter()
-                .filter(|t| *t != 0.0)
-                .map(|p| {
-                    let p_of_this_pattern = p as f64 / remaining_p as f64;
+                .iter()
+                .filter(|t| **t != 0.0)
+                .map(|&p| {
+                    let p_of_this_pattern = p / remaining_p;
                     p_of_this_pattern * p_of_this_pattern.log2()
                 })
                 .sum();
 
             let p_word = if in_remaining {
-                count as f64 / remaining_p as f64
+                count / remaining_p
             } else {
                 0.0
             };
             let e_info = -sum;
             let goodness = match self.options.rank_by {
-                Rank::First => unreachable!("early return above"),
+                Rank::First => p_word,
                 Rank::ExpectedScore => {
                     // NOTE: Higher is better, so we negate the result
                     -(p_word * (score + 1.0)
-                        + (1.0 - p_word) * (score + est_steps_left(remaining_entropy - e_info)))
+                        + (1.0 - p_word)
+                            * (score
+                                + self
+                                    .options
+                                    .estimator
+                                    .steps_left(remaining_entropy - e_info)))
                 }
                 Rank::WeightedInformation => p_word * e_info,
                 Rank::InfoPlusProbability => p_word + e_info,
                 Rank::ExpectedInformation => e_info,
+                Rank::Minimax => {
+                    let largest = totals
+                        .iter()
+                        .enumerate()
+                        .filter(|&(idx, _)| idx != solved)
+                        .fold(0.0f64, |largest, (_, &p)| largest.max(p));
+                    if in_remaining {
+                        -largest / remaining_p + MINIMAX_TIE
+                    } else {
+                        -largest / remaining_p
+                    }
+                }
+                Rank::MostParts => {
+                    let parts = totals.iter().filter(|t| **t != 0.0).count() as f64;
+                    // Half a part is less than any difference in parts, so this only breaks ties.
+                    if in_remaining {
+                        parts + 0.5
+                    } else {
+                        parts
+                    }
+                }
             };
-            if let Some(c) = best {
-                if goodness > c.goodness {
-                    best = Some(Candidate {
-                        word,
-                        goodness,
-                        idx: word_idx,
-                    });
+            ranked.push(Candidate {
+                goodness,
+                idx: word_idx,
+            });
+
+            if adaptive {
+                best = best.max(goodness);
+                let rest = largest_from
+                    .get(position + 1)
+                    .map_or(0.0, |p| p / remaining_p);
+                // A little slack, so that rounding never stops the search early.
+                if self.bound(rest, score, remaining_entropy, remaining_p) < best - 1e-9 {
+                    break;
                 }
-            } else {
-                best = Some(Candidate {
-                    word,
-                    goodness,
-                    idx: word_idx,
-                });
             }
-
-            if self.options.cutoff && in_remaining {
+            if cutoff && in_remaining {
                 i += 1;
                 if i >= stop {
                     break;
                 }
             }
+            // A guess that tells nothing apart has no goodness under some ranks, so the search goes
+            // on past the deadline until there is a guess worth making.
+            informative |= goodness != 0.0;
+            if informative && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
         }
-        let best = best.unwrap();
-        assert_ne!(best.goodness, 0.0);
-        self.last_guess_idx = Some(best.idx);
-        best.word.to_string()
+        ranked
+    }
+
+    /// An upper bound on the goodness of any guess that is the answer with probability at most
+    /// `p_word` after `score` guesses.
+    ///
+    /// No guess reveals more information than the entropy that remains, nor more than it takes to
+    /// tell apart every pattern or every candidate.
+    fn bound(&self, p_word: f64, score: f64, remaining_entropy: f64, remaining_p: f64) -> f64 {
+        let p_word = p_word.min(1.0);
+        let outcomes = Pattern::<N>::COUNT.min(self.remaining.len());
+        let e_info = remaining_entropy.min((outcomes as f64).log2());
+        match self.options.rank_by {
+            Rank::First => p_word,
+            Rank::ExpectedScore => {
+                let steps_left = self
+                    .options
+                    .estimator
+                    .min_steps_left(remaining_entropy - e_info, remaining_entropy);
+                // The expected score is linear in `p_word`, so it is lowest at one of its ends.
+                let lowest = steps_left.min(p_word + (1.0 - p_word) * steps_left);
+                -(score + lowest)
+            }
+            Rank::WeightedInformation => p_word * e_info,
+            Rank::InfoPlusProbability => p_word + e_info,
+            Rank::ExpectedInformation => e_info,
+            Rank::Minimax => {
+                // The patterns other than the solved one share what is not the guess itself.
+                let unsolved = (remaining_p * (1.0 - p_word)).max(0.0);
+                -unsolved / (Pattern::<N>::COUNT - 1).min(self.remaining.len()) as f64 / remaining_p
+                    + MINIMAX_TIE
+            }
+            Rank::MostParts => outcomes as f64 + 0.5,
+        }
+    }
+
+    /// Order the first [`Options::lookahead_width`] of `ranked`, which is best first, by their
+    /// expected score after `score` guesses looking [`Options::lookahead_depth`] guesses ahead,
+    /// lowest first. Nothing changes without a lookahead.
+    fn look_ahead(&self, ranked: &mut [Candidate], score: f64) {
+        let depth = self.options.lookahead_depth;
+        if depth <= 1 {
+            return;
+        }
+        let width = self.options.lookahead_width.min(ranked.len());
+        let mut expected: Vec<(f64, Candidate)> = ranked[..width]
+            .iter()
+            .map(|&c| (self.expected_score(&self.remaining, c.idx, score, depth), c))
+            .collect();
+        // Fewest expected guesses first, with ties left in their order by goodness.
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, c)) in ranked.iter_mut().zip(expected) {
+            *slot = c;
+        }
+    }
+
+    /// The expected score if `guess` is played after `score` guesses while `set` remains, looking
+    /// `depth` guesses ahead. The guesses after that are estimated with [`Options::estimator`].
+    fn expected_score(&self, set: &[(usize, f64)], guess: usize, score: f64, depth: usize) -> f64 {
+        let total: f64 = set.iter().map(|&(_, p)| p).sum();
+        let solved = Pattern([Correctness::Correct; N]).index();
+        let mut patterns = Vec::with_capacity(set.len());
+        self.options
+            .for_each_pattern(guess, set, |&(idx, p), pattern| {
+                patterns.push((pattern, idx, p))
+            });
+        patterns.sort_by_key(|&(pattern, _, _)| pattern);
+        let buckets = patterns.chunk_by(|a, b| a.0 == b.0);
+        let p_guess = patterns
+            .iter()
+            .find(|&&(pattern, _, _)| pattern == solved)
+            .map_or(0.0, |&(_, _, p)| p / total);
+
+        if depth <= 1 {
+            let entropy = -set
+                .iter()
+                .map(|&(_, p)| (p / total) * (p / total).log2())
+                .sum::<f64>();
+            let e_info = -buckets
+                .map(|bucket| {
+                    let p = bucket.iter().map(|&(_, _, p)| p).sum::<f64>() / total;
+                    p * p.log2()
+                })
+                .sum::<f64>();
+            return p_guess * (score + 1.0)
+                + (1.0 - p_guess) * (score + self.options.estimator.steps_left(entropy - e_info));
+        }
+
+        let mut expected = p_guess * (score + 1.0);
+        let mut bucket = Vec::new();
+        for group in buckets.filter(|group| group[0].0 != solved) {
+            bucket.clear();
+            bucket.extend(group.iter().map(|&(_, idx, p)| (idx, p)));
+            let weight = bucket.iter().map(|&(_, p)| p).sum::<f64>() / total;
+            expected += weight * self.best_score(&bucket, score + 1.0, depth - 1);
+        }
+        expected
     }
 
-    fn finish(&self, guesses: usize) {
-        if PRINT_ESTIMATION {
-            for (i, &entropy) in self.entropy.iter().enumerate() {
-                let guesses_needed = guesses - (i + 1);
-                println!("{} {}", entropy, guesses_needed);
+    /// The best expected score with `set` remaining after `score` guesses, looking `depth` guesses
+    /// ahead from the `lookahead_width` guesses that look best one guess ahead.
+    ///
+    /// Unless the solver may guess any word, only the words of `set` are tried, since they honour
+    /// every hint so far.
+    fn best_score(&self, set: &[(usize, f64)], score: f64, depth: usize) -> f64 {
+        if set.len() == 1 {
+            return score + 1.0;
+        }
+        let consider = match self.options.hard_mode {
+            HardMode::Easy => &*self.initial,
+            HardMode::Hard | HardMode::Strict => set,
+        };
+        let stop = self.options.cutoff_stop(set.len());
+        let mut i = 0;
+        let mut ranked = Vec::new();
+        for &(idx, _) in consider {
+            ranked.push((self.expected_score(set, idx, score, 1), idx));
+            if self.options.cutoff && set.iter().any(|&(candidate, _)| candidate == idx) {
+                i += 1;
+                if i >= stop {
+                    break;
+                }
             }
         }
+        if depth <= 1 {
+            return ranked
+                .iter()
+                .map(|&(expected, _)| expected)
+                .fold(f64::INFINITY, f64::min);
+        }
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranked
+            .iter()
+            .take(self.options.lookahead_width)
+            .map(|&(_, idx)| self.expected_score(set, idx, score, depth))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl<const N: usize> Guesser<N> for Solver<N> {
+    fn guess(&mut self, history: &[Guess<'_, N>]) -> String {
+        self.try_guess(history).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_guess(&mut self, history: &[Guess<'_, N>]) -> Result<String, Error> {
+        Solver::try_guess(self, history)
     }
 }